@@ -0,0 +1,46 @@
+/// Checksum algorithm of the simulated HIRT header, computed over the packet payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Checksum {
+    /// 16-bit one's complement sum, as used by IP and UDP. Misses some multi-bit errors.
+    Internet,
+
+    /// CRC-32 (IEEE 802.3).
+    Crc32,
+}
+
+impl Checksum {
+    pub fn compute(&self, data: &[u8]) -> u32 {
+        match self {
+            Self::Internet => {
+                let mut sum: u32 = data
+                    .chunks(2)
+                    .map(|word| u16::from_be_bytes([word[0], *word.get(1).unwrap_or(&0)]) as u32)
+                    .sum();
+                while sum > 0xffff {
+                    sum = (sum & 0xffff) + (sum >> 16);
+                }
+                !sum & 0xffff
+            }
+            Self::Crc32 => {
+                let mut crc = 0xffffffffu32;
+                for byte in data {
+                    crc ^= *byte as u32;
+                    for _ in 0..8 {
+                        let mask = (crc & 1).wrapping_neg();
+                        crc = (crc >> 1) ^ (0xedb88320 & mask);
+                    }
+                }
+                !crc
+            }
+        }
+    }
+}
+
+impl From<&str> for Checksum {
+    fn from(value: &str) -> Self {
+        match value {
+            "internet" => Self::Internet,
+            _ => Self::Crc32,
+        }
+    }
+}
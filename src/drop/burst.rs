@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+use super::DropScheduler;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+#[derive(Clone, Debug)]
+/// Distribution of the length of bursts (consecutive drops) or gaps (consecutive received packets).
+/// Sampled lengths are always at least 1, except for an empty empirical distribution.
+pub enum LengthDistribution {
+    /// Empirical distribution. Contains the lengths and their cumulative weights, in increasing order.
+    /// Without any length, e.g., the bursts of a trace without losses, runs never happen (length 0).
+    Empirical(Vec<(u64, f64)>),
+
+    /// Pareto distribution with a minimum length `scale` and a tail index `shape`.
+    Pareto { scale: f64, shape: f64 },
+
+    /// Log-normal distribution, i.e., the length is `exp(X)` with `X` following `N(mu, sigma^2)`.
+    LogNormal { mu: f64, sigma: f64 },
+}
+
+impl LengthDistribution {
+    /// Empirical distribution from a histogram mapping a length to its weight, e.g., as computed by [`super::fit::LossModelFit`].
+    pub fn empirical(histogram: &BTreeMap<u64, u64>) -> Self {
+        Self::from_weights(histogram.iter().map(|(&len, &nb)| (len, nb as f64)))
+    }
+
+    /// Pareto distribution. Fails unless both parameters are positive.
+    pub fn pareto(scale: f64, shape: f64) -> Result<Self, String> {
+        if !(scale > 0.0 && scale.is_finite() && shape > 0.0 && shape.is_finite()) {
+            return Err(format!("Wrong Pareto parameters: {}:{}", scale, shape));
+        }
+        Ok(Self::Pareto { scale, shape })
+    }
+
+    /// Log-normal distribution. Fails unless `mu` is finite and `sigma` is non-negative.
+    pub fn lognormal(mu: f64, sigma: f64) -> Result<Self, String> {
+        if !(mu.is_finite() && sigma >= 0.0 && sigma.is_finite()) {
+            return Err(format!("Wrong log-normal parameters: {}:{}", mu, sigma));
+        }
+        Ok(Self::LogNormal { mu, sigma })
+    }
+
+    /// Whether runs never happen, see [`Self::Empirical`].
+    pub fn is_empty(&self) -> bool {
+        matches!(self, Self::Empirical(cumulative) if cumulative.is_empty())
+    }
+
+    fn from_weights(weights: impl Iterator<Item = (u64, f64)>) -> Self {
+        let mut total = 0.0;
+        Self::Empirical(
+            weights
+                .filter(|(len, weight)| *len > 0 && *weight > 0.0)
+                .map(|(len, weight)| {
+                    total += weight;
+                    (len, total)
+                })
+                .collect(),
+        )
+    }
+
+    /// Draws a new length.
+    pub fn sample(&self, rng: &mut SmallRng) -> u64 {
+        let len = match self {
+            Self::Empirical(cumulative) => {
+                let total = match cumulative.last() {
+                    Some((_, total)) => *total,
+                    None => return 0,
+                };
+                let target = rng.gen::<f64>() * total;
+                let idx = cumulative.partition_point(|(_, cum)| *cum <= target);
+                cumulative[idx.min(cumulative.len() - 1)].0 as f64
+            }
+            Self::Pareto { scale, shape } => {
+                // Inverse transform sampling. `1 - gen()` lies in (0, 1].
+                let u = 1.0 - rng.gen::<f64>();
+                scale / u.powf(1.0 / shape)
+            }
+            Self::LogNormal { mu, sigma } => {
+                // Box-Muller transform to draw from a standard normal distribution.
+                let u1 = 1.0 - rng.gen::<f64>();
+                let u2 = rng.gen::<f64>();
+                let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                (mu + sigma * z).exp()
+            }
+        };
+
+        len.floor().max(1.0) as u64
+    }
+}
+
+impl FromStr for LengthDistribution {
+    type Err = String;
+
+    /// Parses `pareto:<scale>:<shape>`, `lognormal:<mu>:<sigma>` or `empirical:<len>=<weight>,<len>=<weight>,...`.
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        let (kind, params) = value
+            .split_once(':')
+            .ok_or(format!("Missing distribution parameters: {}", value))?;
+        let parse = |v: &str| {
+            v.parse::<f64>()
+                .map_err(|e| format!("Wrong parameter {}: {}", v, e))
+        };
+
+        match kind {
+            "pareto" | "lognormal" => {
+                let (a, b) = params
+                    .split_once(':')
+                    .ok_or(format!("Expected two parameters: {}", value))?;
+                let (a, b) = (parse(a)?, parse(b)?);
+                if kind == "pareto" {
+                    Self::pareto(a, b)
+                } else {
+                    Self::lognormal(a, b)
+                }
+            }
+            "empirical" => {
+                let weights = params
+                    .split(',')
+                    .map(|item| {
+                        let (len, weight) = item
+                            .split_once('=')
+                            .ok_or(format!("Expected <len>=<weight>: {}", item))?;
+                        let len = len
+                            .parse::<u64>()
+                            .map_err(|e| format!("Wrong length {}: {}", len, e))?;
+                        let weight = parse(weight)?;
+                        if !(weight >= 0.0 && weight.is_finite()) {
+                            return Err(format!("Wrong weight: {}", item));
+                        }
+                        Ok((len, weight))
+                    })
+                    .collect::<std::result::Result<Vec<_>, String>>()?;
+                let distribution = Self::from_weights(weights.into_iter());
+                if distribution.is_empty() {
+                    return Err(format!("No positive length and weight: {}", value));
+                }
+                Ok(distribution)
+            }
+            _ => Err(format!("Unknown distribution: {}", kind)),
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Drops packets in bursts. Alternates between gaps and bursts whose lengths are drawn from two distributions.
+pub struct BurstDropScheduler {
+    /// Distribution of the number of consecutive received packets.
+    gaps: LengthDistribution,
+
+    /// Distribution of the number of consecutive dropped packets.
+    bursts: LengthDistribution,
+
+    /// Whether the current run of packets is a burst.
+    in_burst: bool,
+
+    /// Number of packets remaining in the current run.
+    remaining: u64,
+
+    /// Random number generator.
+    rng: SmallRng,
+}
+
+impl DropScheduler for BurstDropScheduler {
+    fn should_drop(&mut self) -> bool {
+        // Without any gap nor burst, e.g., for an empty trace, nothing is dropped.
+        if self.gaps.is_empty() && self.bursts.is_empty() {
+            return false;
+        }
+        while self.remaining == 0 {
+            self.in_burst = !self.in_burst;
+            let distribution = if self.in_burst {
+                &self.bursts
+            } else {
+                &self.gaps
+            };
+            self.remaining = distribution.sample(&mut self.rng);
+        }
+        self.remaining -= 1;
+        self.in_burst
+    }
+
+    /// Repair symbols are dropped during the burst of the last packet.
+    fn drop_probability_at(&mut self, _now: Duration) -> f64 {
+        if self.in_burst && !self.bursts.is_empty() {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+impl BurstDropScheduler {
+    /// The scheduler starts with a gap.
+    pub fn new(gaps: LengthDistribution, bursts: LengthDistribution, seed: u64) -> Self {
+        Self {
+            gaps,
+            bursts,
+            in_burst: true,
+            remaining: 0,
+            rng: SmallRng::seed_from_u64(seed),
+        }
+    }
+}
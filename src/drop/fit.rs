@@ -0,0 +1,289 @@
+use std::collections::BTreeMap;
+
+/// Maximum number of Baum-Welch iterations to fit the full Gilbert-Elliot model.
+const MAX_EM_ITERATIONS: usize = 200;
+
+/// Stop the Baum-Welch iterations when the log-likelihood improves less than this value.
+const EM_TOLERANCE: f64 = 1e-6;
+
+#[derive(Clone, Debug, PartialEq)]
+/// Parameters of a Gilbert-Elliot loss process, using the same names as [`super::ge::GilbertEliotDropSheduler`].
+pub struct GilbertEliotParams {
+    /// Probability to move from the `Keep` state to the `Drop` state.
+    pub g2b: f64,
+
+    /// Probability to move from the `Drop` state to the `Keep` state.
+    pub b2g: f64,
+
+    /// Probability to drop a packet in the `Keep` state.
+    pub dg: f64,
+
+    /// Probability to drop a packet in the `Drop` state.
+    pub db: f64,
+}
+
+/// Loss-model parameters estimated from a drop trace.
+pub struct LossModelFit {
+    /// Number of packets in the trace.
+    nb_pkts: u64,
+
+    /// Number of dropped packets in the trace.
+    nb_dropped: u64,
+
+    /// Simple Gilbert model, i.e., a Gilbert-Elliot model where `dg = 0` and `db = 1`.
+    gilbert: GilbertEliotParams,
+
+    /// Full Gilbert-Elliot model fitted with the Baum-Welch algorithm.
+    gilbert_eliot: GilbertEliotParams,
+
+    /// Log-likelihood of the trace under the fitted Gilbert-Elliot model.
+    log_likelihood: f64,
+
+    /// Histogram of burst lengths (consecutive drops). Maps a length to its number of occurrences.
+    bursts: BTreeMap<u64, u64>,
+
+    /// Histogram of gap lengths (consecutive received packets). Maps a length to its number of occurrences.
+    gaps: BTreeMap<u64, u64>,
+}
+
+impl LossModelFit {
+    /// Fits all loss models from a drop trace. Each element of `drops` tells whether the packet was dropped.
+    pub fn new(drops: &[bool]) -> Self {
+        let nb_dropped = drops.iter().filter(|d| **d).count() as u64;
+        let (bursts, gaps) = run_lengths(drops);
+        let gilbert = fit_gilbert(drops);
+        let (gilbert_eliot, log_likelihood) = fit_gilbert_eliot(drops, &gilbert);
+
+        Self {
+            nb_pkts: drops.len() as u64,
+            nb_dropped,
+            gilbert,
+            gilbert_eliot,
+            log_likelihood,
+            bursts,
+            gaps,
+        }
+    }
+
+    pub fn get_nb_pkts(&self) -> u64 {
+        self.nb_pkts
+    }
+
+    pub fn get_nb_dropped(&self) -> u64 {
+        self.nb_dropped
+    }
+
+    /// Uniform drop rate of the trace.
+    pub fn get_uniform_rate(&self) -> f64 {
+        if self.nb_pkts == 0 {
+            0.0
+        } else {
+            self.nb_dropped as f64 / self.nb_pkts as f64
+        }
+    }
+
+    pub fn get_gilbert(&self) -> &GilbertEliotParams {
+        &self.gilbert
+    }
+
+    pub fn get_gilbert_eliot(&self) -> &GilbertEliotParams {
+        &self.gilbert_eliot
+    }
+
+    pub fn get_log_likelihood(&self) -> f64 {
+        self.log_likelihood
+    }
+
+    pub fn get_bursts(&self) -> &BTreeMap<u64, u64> {
+        &self.bursts
+    }
+
+    pub fn get_gaps(&self) -> &BTreeMap<u64, u64> {
+        &self.gaps
+    }
+
+    /// Mean number of consecutive drops.
+    pub fn get_mean_burst(&self) -> f64 {
+        mean_length(&self.bursts)
+    }
+
+    /// Mean number of consecutive received packets.
+    pub fn get_mean_gap(&self) -> f64 {
+        mean_length(&self.gaps)
+    }
+}
+
+/// Computes the histograms of burst and gap lengths.
+fn run_lengths(drops: &[bool]) -> (BTreeMap<u64, u64>, BTreeMap<u64, u64>) {
+    let mut bursts = BTreeMap::new();
+    let mut gaps = BTreeMap::new();
+
+    let mut iter = drops.iter().peekable();
+    while let Some(&current) = iter.next() {
+        let mut len = 1;
+        while iter.next_if(|&&next| next == current).is_some() {
+            len += 1;
+        }
+        let histogram = if current { &mut bursts } else { &mut gaps };
+        *histogram.entry(len).or_insert(0) += 1;
+    }
+
+    (bursts, gaps)
+}
+
+fn mean_length(histogram: &BTreeMap<u64, u64>) -> f64 {
+    let nb: u64 = histogram.values().sum();
+    if nb == 0 {
+        return 0.0;
+    }
+    histogram.iter().map(|(len, n)| len * n).sum::<u64>() as f64 / nb as f64
+}
+
+/// Fits the simple Gilbert model by counting transitions between received and dropped packets.
+fn fit_gilbert(drops: &[bool]) -> GilbertEliotParams {
+    // Transition counts, indexed by [from][to].
+    let mut transitions = [[0u64; 2]; 2];
+    for pair in drops.windows(2) {
+        transitions[pair[0] as usize][pair[1] as usize] += 1;
+    }
+
+    let from_keep = transitions[0][0] + transitions[0][1];
+    let from_drop = transitions[1][0] + transitions[1][1];
+    GilbertEliotParams {
+        // Without any received packet to leave from, the channel is always bad if it drops at all.
+        g2b: if from_keep == 0 {
+            if drops.contains(&true) { 1.0 } else { 0.0 }
+        } else {
+            transitions[0][1] as f64 / from_keep as f64
+        },
+        b2g: if from_drop == 0 {
+            1.0
+        } else {
+            transitions[1][0] as f64 / from_drop as f64
+        },
+        dg: 0.0,
+        db: 1.0,
+    }
+}
+
+/// Fits the full Gilbert-Elliot model with the Baum-Welch algorithm, starting from the simple Gilbert estimation.
+/// Returns the parameters and the log-likelihood of the trace.
+fn fit_gilbert_eliot(drops: &[bool], gilbert: &GilbertEliotParams) -> (GilbertEliotParams, f64) {
+    let n = drops.len();
+    if n < 2 || drops.iter().all(|d| !d) || drops.iter().all(|d| *d) {
+        // Degenerate trace: the simple Gilbert model is already exact.
+        return (gilbert.clone(), 0.0);
+    }
+
+    // State 0 is `Keep`, state 1 is `Drop`.
+    // Start slightly away from the borders, otherwise the zero probabilities remain zero.
+    let g2b = gilbert.g2b.clamp(0.01, 0.99);
+    let b2g = gilbert.b2g.clamp(0.01, 0.99);
+    let mut pi = [0.5, 0.5];
+    let mut trans = [[1.0 - g2b, g2b], [b2g, 1.0 - b2g]];
+    let mut emit = [0.01, 0.99];
+
+    let mut alpha = vec![[0.0; 2]; n];
+    let mut beta = vec![[0.0; 2]; n];
+    let mut scale = vec![0.0; n];
+    let mut log_likelihood = f64::NEG_INFINITY;
+
+    let e = |emit: &[f64; 2], state: usize, drop: bool| {
+        if drop {
+            emit[state]
+        } else {
+            1.0 - emit[state]
+        }
+    };
+
+    for _ in 0..MAX_EM_ITERATIONS {
+        // Forward pass with scaling.
+        for s in 0..2 {
+            alpha[0][s] = pi[s] * e(&emit, s, drops[0]);
+        }
+        scale[0] = alpha[0][0] + alpha[0][1];
+        alpha[0][0] /= scale[0];
+        alpha[0][1] /= scale[0];
+        for t in 1..n {
+            for s in 0..2 {
+                alpha[t][s] = (alpha[t - 1][0] * trans[0][s] + alpha[t - 1][1] * trans[1][s])
+                    * e(&emit, s, drops[t]);
+            }
+            scale[t] = alpha[t][0] + alpha[t][1];
+            alpha[t][0] /= scale[t];
+            alpha[t][1] /= scale[t];
+        }
+
+        // Backward pass using the same scaling factors.
+        beta[n - 1] = [1.0, 1.0];
+        for t in (0..n - 1).rev() {
+            for s in 0..2 {
+                beta[t][s] = (0..2)
+                    .map(|next| trans[s][next] * e(&emit, next, drops[t + 1]) * beta[t + 1][next])
+                    .sum::<f64>()
+                    / scale[t + 1];
+            }
+        }
+
+        // Accumulate the expected counts.
+        let mut gamma_sum = [0.0; 2];
+        let mut gamma_drop = [0.0; 2];
+        let mut gamma_from = [0.0; 2];
+        let mut xi_sum = [[0.0; 2]; 2];
+        for t in 0..n {
+            let norm = alpha[t][0] * beta[t][0] + alpha[t][1] * beta[t][1];
+            for s in 0..2 {
+                let gamma = alpha[t][s] * beta[t][s] / norm;
+                gamma_sum[s] += gamma;
+                if drops[t] {
+                    gamma_drop[s] += gamma;
+                }
+                if t + 1 < n {
+                    gamma_from[s] += gamma;
+                    for next in 0..2 {
+                        xi_sum[s][next] += alpha[t][s]
+                            * trans[s][next]
+                            * e(&emit, next, drops[t + 1])
+                            * beta[t + 1][next]
+                            / scale[t + 1];
+                    }
+                }
+            }
+            if t == 0 {
+                pi = [
+                    alpha[0][0] * beta[0][0] / norm,
+                    alpha[0][1] * beta[0][1] / norm,
+                ];
+            }
+        }
+
+        // Maximisation step.
+        for s in 0..2 {
+            if gamma_from[s] > 0.0 {
+                let total = xi_sum[s][0] + xi_sum[s][1];
+                trans[s] = [xi_sum[s][0] / total, xi_sum[s][1] / total];
+            }
+            if gamma_sum[s] > 0.0 {
+                emit[s] = gamma_drop[s] / gamma_sum[s];
+            }
+        }
+
+        let new_log_likelihood: f64 = scale.iter().map(|c| c.ln()).sum();
+        let converged = (new_log_likelihood - log_likelihood).abs() < EM_TOLERANCE;
+        log_likelihood = new_log_likelihood;
+        if converged {
+            break;
+        }
+    }
+
+    // The `Drop` state is, by convention, the one with the highest drop probability.
+    let (keep, drop) = if emit[0] <= emit[1] { (0, 1) } else { (1, 0) };
+    let params = GilbertEliotParams {
+        g2b: trans[keep][drop].clamp(0.0, 1.0),
+        b2g: trans[drop][keep].clamp(0.0, 1.0),
+        dg: emit[keep].clamp(0.0, 1.0),
+        db: emit[drop].clamp(0.0, 1.0),
+    };
+
+    (params, log_likelihood)
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use super::DropScheduler;
 use rand::rngs::SmallRng;
 use rand::{Rng, SeedableRng};
@@ -20,13 +22,16 @@ pub struct GilbertEliotDropSheduler {
     b2g: f64,
 
     /// Probability to drop a packet in the `Keep` state.
-    /// Currently always set to 0.
+    /// Set to 0 with [`Self::new_simple`].
     dg: f64,
 
     /// Probability to drop a packet in the `Drop` state.
-    /// Currently always set to 1.
+    /// Set to 1 with [`Self::new_simple`].
     db: f64,
 
+    /// Drop probability of the last packet, i.e., of its state before the transition.
+    last_proba_drop: f64,
+
     /// Random number generator.
     rng: SmallRng,
 }
@@ -40,8 +45,14 @@ impl DropScheduler for GilbertEliotDropSheduler {
         if self.rng.gen_bool(proba_change) {
             self.change_state();
         }
+        self.last_proba_drop = proba_drop;
         self.rng.gen_bool(proba_drop)
     }
+
+    /// Drop probability of the state of the last packet.
+    fn drop_probability_at(&mut self, _now: Duration) -> f64 {
+        self.last_proba_drop
+    }
 }
 
 impl GilbertEliotDropSheduler {
@@ -53,12 +64,19 @@ impl GilbertEliotDropSheduler {
     }
 
     pub fn new_simple(g2b: f64, b2g: f64, seed: u64) -> Self {
+        Self::new(g2b, b2g, 0.0, 1.0, seed)
+    }
+
+    /// Full Gilbert-Elliot model where packets may also be dropped in the `Keep` state,
+    /// and may be received in the `Drop` state.
+    pub fn new(g2b: f64, b2g: f64, dg: f64, db: f64, seed: u64) -> Self {
         Self {
             state: State::Keep,
             g2b,
             b2g,
-            dg: 0.0,
-            db: 1.0,
+            dg,
+            db,
+            last_proba_drop: dg,
             rng: SmallRng::seed_from_u64(seed),
         }
     }
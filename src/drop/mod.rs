@@ -1,11 +1,32 @@
 use std::fmt::Debug;
+use std::time::Duration;
 
 pub trait DropScheduler: Debug {
     fn should_drop(&mut self) -> bool;
+
+    /// Whether the packet transmitted at the virtual time `now` should be dropped.
+    /// By default, the scheduler evolves per packet and ignores the time.
+    fn should_drop_at(&mut self, now: Duration) -> bool {
+        let _ = now;
+        self.should_drop()
+    }
+
+    /// Probability to drop a packet transmitted at the virtual time `now` in the current channel
+    /// state, without drawing the drop decision nor advancing the state on a per-packet basis.
+    /// With common random numbers, repair symbols share the channel state of the source symbols
+    /// this way, but draw their drop decisions from their own stream.
+    /// By default, repair symbols are never dropped, e.g., for deterministic loss patterns.
+    fn drop_probability_at(&mut self, now: Duration) -> f64 {
+        let _ = now;
+        0.0
+    }
 }
 
 pub mod constant;
 pub mod uniform;
 pub mod none;
 pub mod specific;
-pub mod ge;
\ No newline at end of file
+pub mod ge;
+pub mod fit;
+pub mod burst;
+pub mod time;
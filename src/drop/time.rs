@@ -0,0 +1,172 @@
+use std::time::Duration;
+
+use super::burst::LengthDistribution;
+use super::DropScheduler;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+#[derive(Debug)]
+/// Gilbert-Elliot model whose state evolves in virtual time, i.e., one transition every `step`,
+/// instead of once per packet. Packets are dropped based on the state at their transmission time.
+pub struct TimeGilbertEliotDropScheduler {
+    /// Whether the current state is the `Drop` state.
+    in_drop: bool,
+
+    /// Probability to move from the `Keep` state to the `Drop` state during a step.
+    g2b: f64,
+
+    /// Probability to move from the `Drop` state to the `Keep` state during a step.
+    b2g: f64,
+
+    /// Probability to drop a packet in the `Keep` state.
+    dg: f64,
+
+    /// Probability to drop a packet in the `Drop` state.
+    db: f64,
+
+    /// Duration of a step.
+    step: Duration,
+
+    /// Virtual time of the last state transition.
+    last: Duration,
+
+    /// Random number generator for state transitions, drawn once per step. Independent of the packets.
+    state_rng: SmallRng,
+
+    /// Random number generator for the drop decisions.
+    drop_rng: SmallRng,
+}
+
+impl DropScheduler for TimeGilbertEliotDropScheduler {
+    /// Without time information, the packet is dropped based on the current state.
+    fn should_drop(&mut self) -> bool {
+        let proba_drop = if self.in_drop { self.db } else { self.dg };
+        proba_drop >= 1.0 || (proba_drop > 0.0 && self.drop_rng.gen_bool(proba_drop))
+    }
+
+    fn should_drop_at(&mut self, now: Duration) -> bool {
+        self.advance(now);
+        self.should_drop()
+    }
+
+    fn drop_probability_at(&mut self, now: Duration) -> f64 {
+        self.advance(now);
+        if self.in_drop {
+            self.db
+        } else {
+            self.dg
+        }
+    }
+}
+
+impl TimeGilbertEliotDropScheduler {
+    pub fn new(g2b: f64, b2g: f64, dg: f64, db: f64, step: Duration, seed: u64) -> Self {
+        Self {
+            in_drop: false,
+            g2b,
+            b2g,
+            dg,
+            db,
+            step: step.max(Duration::from_nanos(1)),
+            last: Duration::ZERO,
+            state_rng: SmallRng::seed_from_u64(seed),
+            drop_rng: SmallRng::seed_from_u64(seed.wrapping_add(1)),
+        }
+    }
+
+    pub fn new_simple(g2b: f64, b2g: f64, step: Duration, seed: u64) -> Self {
+        Self::new(g2b, b2g, 0.0, 1.0, step, seed)
+    }
+
+    /// Applies all transitions that happened between the last transition and `now`, one per step,
+    /// so that the channel state does not depend on the transmission times of the packets.
+    fn advance(&mut self, now: Duration) {
+        while now.saturating_sub(self.last) >= self.step {
+            self.last += self.step;
+            let proba_change = if self.in_drop { self.b2g } else { self.g2b };
+            if self.state_rng.gen_bool(proba_change) {
+                self.in_drop = !self.in_drop;
+            }
+        }
+    }
+}
+
+/// Duration of `n` times `unit`, saturating instead of overflowing.
+fn mul_duration(unit: Duration, n: u64) -> Duration {
+    let nanos = unit.as_nanos().saturating_mul(n as u128);
+    Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+}
+
+#[derive(Debug)]
+/// Link alternating between up periods and outages, whose durations are drawn from two distributions.
+/// All packets transmitted during an outage are dropped.
+pub struct OutageDropScheduler {
+    /// Distribution of the duration of up periods, in `unit`.
+    up: LengthDistribution,
+
+    /// Distribution of the duration of outages, in `unit`.
+    down: LengthDistribution,
+
+    /// Time unit of the distributions.
+    unit: Duration,
+
+    /// Whether the link is currently down.
+    in_outage: bool,
+
+    /// Virtual time at which the current period ends.
+    end: Duration,
+
+    /// Random number generator.
+    rng: SmallRng,
+}
+
+impl DropScheduler for OutageDropScheduler {
+    /// Without time information, the packet is dropped if the link is currently down.
+    fn should_drop(&mut self) -> bool {
+        self.in_outage
+    }
+
+    fn should_drop_at(&mut self, now: Duration) -> bool {
+        if self.up.is_empty() && self.down.is_empty() {
+            return false;
+        }
+        while now >= self.end {
+            self.in_outage = !self.in_outage;
+            let distribution = if self.in_outage {
+                &self.down
+            } else {
+                &self.up
+            };
+            let len = distribution.sample(&mut self.rng);
+            self.end = self.end.saturating_add(mul_duration(self.unit, len));
+        }
+        self.in_outage
+    }
+
+    fn drop_probability_at(&mut self, now: Duration) -> f64 {
+        if self.should_drop_at(now) {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+impl OutageDropScheduler {
+    /// The link starts with an up period at time 0.
+    pub fn new(
+        up: LengthDistribution,
+        down: LengthDistribution,
+        unit: Duration,
+        seed: u64,
+    ) -> Self {
+        Self {
+            up,
+            down,
+            unit: unit.max(Duration::from_nanos(1)),
+            in_outage: true,
+            end: Duration::ZERO,
+            rng: SmallRng::seed_from_u64(seed),
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use super::DropScheduler;
 use rand::rngs::SmallRng;
 use rand::{Rng, SeedableRng};
@@ -13,6 +15,10 @@ impl DropScheduler for UniformDropScheduler {
     fn should_drop(&mut self) -> bool {
         self.rng.gen_bool(self.rate)
     }
+
+    fn drop_probability_at(&mut self, _now: Duration) -> f64 {
+        self.rate
+    }
 }
 
 impl UniformDropScheduler {
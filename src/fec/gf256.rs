@@ -0,0 +1,73 @@
+//! Arithmetic in GF(2^8), shared by the in-crate codes.
+
+/// Primitive polynomial `x^8 + x^4 + x^3 + x^2 + 1` generating GF(2^8).
+const GF_POLYNOMIAL: u16 = 0x11d;
+
+/// Exponential (doubled to avoid a modulo in the multiplication) and logarithm tables of GF(2^8).
+const GF_TABLES: ([u8; 512], [u8; 256]) = gf_tables();
+
+const fn gf_tables() -> ([u8; 512], [u8; 256]) {
+    let mut exp = [0u8; 512];
+    let mut log = [0u8; 256];
+    let mut x: u16 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = x as u8;
+        log[x as usize] = i as u8;
+        x <<= 1;
+        if x & 0x100 != 0 {
+            x ^= GF_POLYNOMIAL;
+        }
+        i += 1;
+    }
+    while i < 512 {
+        exp[i] = exp[i - 255];
+        i += 1;
+    }
+    (exp, log)
+}
+
+pub(crate) fn mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        0
+    } else {
+        GF_TABLES.0[GF_TABLES.1[a as usize] as usize + GF_TABLES.1[b as usize] as usize]
+    }
+}
+
+/// Multiplicative inverse. `a` must not be zero.
+pub(crate) fn inv(a: u8) -> u8 {
+    GF_TABLES.0[255 - GF_TABLES.1[a as usize] as usize]
+}
+
+/// `dst += coef * src`, byte by byte. `dst` is padded with zeros if it is shorter than `src`.
+pub(crate) fn add_mul(dst: &mut Vec<u8>, src: &[u8], coef: u8) {
+    if coef == 0 {
+        return;
+    }
+    if dst.len() < src.len() {
+        dst.resize(src.len(), 0);
+    }
+    dst.iter_mut()
+        .zip(src)
+        .for_each(|(d, s)| *d ^= mul(coef, *s));
+}
+
+/// `dst *= coef`, byte by byte.
+pub(crate) fn scale(dst: &mut [u8], coef: u8) {
+    dst.iter_mut().for_each(|d| *d = mul(*d, coef));
+}
+
+/// Coded form of a payload: its length on two bytes followed by the payload,
+/// so that payloads of different lengths can be recovered after padding.
+pub(crate) fn to_symbol(data: &[u8]) -> Vec<u8> {
+    let mut symbol = (data.len() as u16).to_be_bytes().to_vec();
+    symbol.extend_from_slice(data);
+    symbol
+}
+
+/// Payload of a coded symbol, without the padding.
+pub(crate) fn from_symbol(symbol: &[u8]) -> Option<Vec<u8>> {
+    let len = u16::from_be_bytes(symbol.get(..2)?.try_into().ok()?) as usize;
+    symbol.get(2..2 + len).map(|data| data.to_vec())
+}
@@ -0,0 +1,592 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::fmt::Debug;
+
+use rand::rngs::SmallRng;
+use rand::seq::index::sample;
+use rand::{Rng, SeedableRng};
+
+use super::gf256::{self, from_symbol, to_symbol};
+use super::maelstrom::repair_id;
+use super::FecDecoderScheme;
+use super::FecEncoderScheme;
+use super::FecMetadata;
+use super::FecParams;
+use super::FecRepairMetadata;
+use super::FecSourceMetadata;
+use super::FecStats;
+use crate::node::decoder::Decoder as DecoderNode;
+use crate::node::encoder::Encoder as EncoderNode;
+use crate::Error;
+use crate::Packet;
+use crate::Result;
+
+pub type LtSSID = u64;
+
+/// Number of precode parities each source symbol is connected to.
+const PRECODE_DEGREE: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LtVariant {
+    /// Encoding symbols combine the source symbols of a block, and optionally the precode parities of the block.
+    Block,
+
+    /// Encoding symbols combine the last source symbols.
+    Window,
+}
+
+impl TryFrom<&str> for LtVariant {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self> {
+        match value {
+            "block" => Ok(Self::Block),
+            "window" => Ok(Self::Window),
+            _ => Err(Error::FecEncoder(format!("Unknown LT variant: {}", value))),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+/// LT repair FEC information. The neighbours of the encoding symbol are derived from the seed.
+pub struct LtRepairInfo {
+    /// ID of the first source symbol of the block or window.
+    first_id: LtSSID,
+
+    /// Number of source symbols of the block or window.
+    nb_symbols: usize,
+
+    /// Number of precode parities of the block.
+    precode: usize,
+
+    /// Seed of the degree and neighbours.
+    seed: u64,
+}
+
+/// Robust soliton distribution, with cached cumulative distributions by number of input symbols.
+struct RobustSoliton {
+    c: f64,
+
+    delta: f64,
+
+    cumulative: HashMap<usize, Vec<f64>>,
+}
+
+impl RobustSoliton {
+    fn new(c: f64, delta: f64) -> Self {
+        Self {
+            c,
+            delta,
+            cumulative: HashMap::new(),
+        }
+    }
+
+    fn compute(k: usize, c: f64, delta: f64) -> Vec<f64> {
+        let kf = k as f64;
+        let r = (c * (kf / delta).ln() * kf.sqrt()).max(1.0);
+        let spike = ((kf / r).floor() as usize).clamp(1, k);
+
+        let weights: Vec<f64> = (1..=k)
+            .map(|d| {
+                let rho = if d == 1 {
+                    1.0 / kf
+                } else {
+                    1.0 / (d * (d - 1)) as f64
+                };
+                let tau = if d < spike {
+                    r / (d as f64 * kf)
+                } else if d == spike {
+                    r * (r / delta).ln().max(0.0) / kf
+                } else {
+                    0.0
+                };
+                rho + tau
+            })
+            .collect();
+
+        let total: f64 = weights.iter().sum();
+        let mut cum = 0.0;
+        weights
+            .iter()
+            .map(|w| {
+                cum += w / total;
+                cum
+            })
+            .collect()
+    }
+
+    /// Neighbours, among `k` input symbols, of the encoding symbol generated from `seed`.
+    fn neighbours(&mut self, seed: u64, k: usize) -> Vec<usize> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let (c, delta) = (self.c, self.delta);
+        let cumulative = self
+            .cumulative
+            .entry(k)
+            .or_insert_with(|| Self::compute(k, c, delta));
+
+        let mut rng = SmallRng::seed_from_u64(seed);
+        let u: f64 = rng.gen();
+        let degree = (cumulative.partition_point(|cum| *cum < u) + 1).min(k);
+        sample(&mut rng, k, degree).into_vec()
+    }
+}
+
+/// Precode of a block: the sources connected to each parity. Parity `j` is the XOR of its sources.
+fn precode_graph(first_id: LtSSID, k: usize, precode: usize) -> Vec<Vec<usize>> {
+    let mut graph = vec![Vec::new(); precode];
+    if precode == 0 {
+        return graph;
+    }
+    let mut rng = SmallRng::seed_from_u64(first_id);
+    for source in 0..k {
+        for parity in sample(&mut rng, precode, PRECODE_DEGREE.min(precode)) {
+            graph[parity].push(source);
+        }
+    }
+    graph
+}
+
+/// Input symbol of the LT code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Node {
+    Source(LtSSID),
+
+    /// Precode parity of the block starting at the given source symbol.
+    Parity(LtSSID, usize),
+}
+
+impl Node {
+    fn from_index(first_id: LtSSID, nb_symbols: usize, index: usize) -> Self {
+        if index < nb_symbols {
+            Self::Source(first_id + index as u64)
+        } else {
+            Self::Parity(first_id, index - nb_symbols)
+        }
+    }
+
+    /// Source symbol ID used to remove old symbols.
+    fn age(&self) -> LtSSID {
+        match self {
+            Self::Source(id) => *id,
+            Self::Parity(first_id, _) => *first_id,
+        }
+    }
+}
+
+/// LT fountain encoder. Generates `overhead` encoding symbols per source symbol,
+/// with a degree drawn from the robust soliton distribution.
+pub struct LtEncoder {
+    variant: LtVariant,
+
+    /// Size of a block, or of the window.
+    k: usize,
+
+    /// Number of encoding symbols per source symbol.
+    overhead: f64,
+
+    /// Number of precode parities per block.
+    precode: usize,
+
+    /// Coded source symbols of the current block or window.
+    symbols: VecDeque<(LtSSID, Vec<u8>)>,
+
+    /// Number of encoding symbols that should be sent.
+    credit: f64,
+
+    /// ID of the next source symbol, used as the first ID of an empty window.
+    next_id: u64,
+
+    /// Number of encoding symbols generated, used as the seed of the next one.
+    nb_rs: u64,
+
+    soliton: RobustSoliton,
+}
+
+impl Debug for LtEncoder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let variant = match self.variant {
+            LtVariant::Block => "block",
+            LtVariant::Window => "window",
+        };
+        write!(f, "lt_{}_{}_{}", variant, self.k, self.overhead)?;
+        if self.precode > 0 {
+            write!(f, "_p{}", self.precode)?;
+        }
+        Ok(())
+    }
+}
+
+impl LtEncoder {
+    pub fn new(variant: LtVariant, k: usize, overhead: f64, precode: usize) -> Self {
+        Self {
+            variant,
+            k: k.max(1),
+            overhead,
+            precode: if variant == LtVariant::Block { precode } else { 0 },
+            symbols: VecDeque::with_capacity(k),
+            credit: 0.0,
+            next_id: 0,
+            nb_rs: 0,
+            soliton: RobustSoliton::new(0.1, 0.5),
+        }
+    }
+
+    /// Sets the parameters of the robust soliton distribution.
+    pub fn set_soliton(&mut self, c: f64, delta: f64) {
+        self.soliton = RobustSoliton::new(c, delta);
+    }
+
+    pub fn get_nb_rs(&self) -> u64 {
+        self.nb_rs
+    }
+
+    fn generate_symbol(&mut self, intermediate: &[Vec<u8>]) -> Packet {
+        let (first_id, nb_symbols) = match self.symbols.front() {
+            Some((id, _)) => (*id, self.symbols.len()),
+            None => (self.next_id, 0),
+        };
+        let seed = self.nb_rs;
+        self.nb_rs += 1;
+
+        let mut data = Vec::new();
+        for index in self.soliton.neighbours(seed, nb_symbols + self.precode) {
+            let symbol = match index.checked_sub(nb_symbols) {
+                None => &self.symbols[index].1,
+                Some(parity) => &intermediate[parity],
+            };
+            gf256::add_mul(&mut data, symbol, 1);
+        }
+
+        let repair_info = LtRepairInfo {
+            first_id,
+            nb_symbols,
+            precode: self.precode,
+            seed,
+        };
+        Packet {
+            id: repair_id(seed),
+            fec: Some(FecMetadata::Repair(FecRepairMetadata::new(repair_info))),
+            recovered: None,
+            data,
+            ..Default::default()
+        }
+    }
+}
+
+impl FecEncoderScheme for LtEncoder {
+    fn protect_symbol(&mut self, pkt: &mut Packet) -> Result<()> {
+        pkt.add_fec_metadata(FecMetadata::Source(FecSourceMetadata::new::<LtSSID>(
+            pkt.id,
+        )))?;
+        self.symbols.push_back((pkt.id, to_symbol(&pkt.data)));
+        self.next_id = pkt.id + 1;
+        self.credit += self.overhead;
+
+        if self.variant == LtVariant::Window && self.symbols.len() > self.k {
+            self.symbols.pop_front();
+        }
+
+        Ok(())
+    }
+
+    fn should_generate_rs(&mut self) -> bool {
+        match self.variant {
+            LtVariant::Block => self.symbols.len() >= self.k,
+            LtVariant::Window => self.credit >= 1.0,
+        }
+    }
+
+    fn generate_rs(&mut self) -> Result<Vec<Packet>> {
+        // Precode parities of the block.
+        let intermediate: Vec<Vec<u8>> = match self.symbols.front() {
+            Some((first_id, _)) => precode_graph(*first_id, self.symbols.len(), self.precode)
+                .iter()
+                .map(|sources| {
+                    let mut parity = Vec::new();
+                    sources
+                        .iter()
+                        .for_each(|&s| gf256::add_mul(&mut parity, &self.symbols[s].1, 1));
+                    parity
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let mut out = Vec::new();
+        while self.credit >= 1.0 {
+            out.push(self.generate_symbol(&intermediate));
+            self.credit -= 1.0;
+        }
+
+        if self.variant == LtVariant::Block {
+            // Rounding: the remaining credit goes to the next block.
+            self.symbols.clear();
+        }
+
+        Ok(out)
+    }
+
+    fn stats(&self) -> FecStats {
+        vec![("repairs".to_string(), self.get_nb_rs())]
+    }
+
+    fn name(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// Encoding symbol or precode constraint whose unknown input symbols are not all known.
+struct Equation {
+    unknown: HashSet<Node>,
+
+    payload: Vec<u8>,
+}
+
+/// LT peeling decoder: an equation with a single unknown input symbol recovers it, which may release other equations.
+pub struct LtDecoder {
+    /// Input symbols received or recovered.
+    known: HashMap<Node, Vec<u8>>,
+
+    /// Equations with at least two unknown input symbols, by ID.
+    equations: HashMap<u64, Equation>,
+
+    /// Equations in which each unknown input symbol appears.
+    index: HashMap<Node, HashSet<u64>>,
+
+    /// ID of the next equation.
+    eq_id: u64,
+
+    /// Blocks whose precode constraints are in the system.
+    precoded: HashSet<LtSSID>,
+
+    /// Maximum number of source symbols stored.
+    capacity: u64,
+
+    /// Highest source symbol ID seen.
+    max_id: u64,
+
+    /// Source symbol ID of the last removal of old symbols.
+    last_prune: u64,
+
+    soliton: RobustSoliton,
+}
+
+impl LtDecoder {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            known: HashMap::new(),
+            equations: HashMap::new(),
+            index: HashMap::new(),
+            eq_id: 0,
+            precoded: HashSet::new(),
+            capacity: capacity.max(1) as u64,
+            max_id: 0,
+            last_prune: 0,
+            soliton: RobustSoliton::new(0.1, 0.5),
+        }
+    }
+
+    /// Sets the parameters of the robust soliton distribution. Must match the encoder.
+    pub fn set_soliton(&mut self, c: f64, delta: f64) {
+        self.soliton = RobustSoliton::new(c, delta);
+    }
+
+    /// Number of equations waiting for missing symbols.
+    pub fn get_nb_equations(&self) -> usize {
+        self.equations.len()
+    }
+
+    /// Adds an equation on the input symbols `nodes`. Returns the recovered source symbols.
+    fn add_equation(&mut self, nodes: Vec<Node>, mut payload: Vec<u8>, from: u64) -> Vec<Packet> {
+        let mut unknown = HashSet::new();
+        for node in nodes {
+            match self.known.get(&node) {
+                Some(symbol) => gf256::add_mul(&mut payload, symbol, 1),
+                None => {
+                    unknown.insert(node);
+                }
+            }
+        }
+
+        match unknown.len() {
+            0 => Vec::new(),
+            1 => {
+                let node = *unknown.iter().next().unwrap();
+                self.add_known(node, payload, from)
+            }
+            _ => {
+                let id = self.eq_id;
+                self.eq_id += 1;
+                for node in unknown.iter() {
+                    self.index.entry(*node).or_default().insert(id);
+                }
+                self.equations.insert(id, Equation { unknown, payload });
+                Vec::new()
+            }
+        }
+    }
+
+    /// Adds an input symbol whose value is known and peels the equations. Returns the recovered source symbols.
+    fn add_known(&mut self, node: Node, symbol: Vec<u8>, from: u64) -> Vec<Packet> {
+        let mut recovered = Vec::new();
+        let mut queue = vec![(node, symbol)];
+
+        while let Some((node, symbol)) = queue.pop() {
+            if self.known.contains_key(&node) {
+                continue;
+            }
+
+            for eq_id in self.index.remove(&node).unwrap_or_default() {
+                if let Some(equation) = self.equations.get_mut(&eq_id) {
+                    equation.unknown.remove(&node);
+                    gf256::add_mul(&mut equation.payload, &symbol, 1);
+                    if equation.unknown.len() <= 1 {
+                        let equation = self.equations.remove(&eq_id).unwrap();
+                        if let Some(&last) = equation.unknown.iter().next() {
+                            if let Some(ids) = self.index.get_mut(&last) {
+                                ids.remove(&eq_id);
+                            }
+                            queue.push((last, equation.payload));
+                        }
+                    }
+                }
+            }
+
+            if let Node::Source(id) = node {
+                if let Some(data) = from_symbol(&symbol) {
+                    recovered.push(Packet {
+                        id,
+                        fec: Some(FecMetadata::Source(FecSourceMetadata::new::<LtSSID>(id))),
+                        recovered: Some(from.saturating_sub(id)),
+                        data,
+                        ..Default::default()
+                    });
+                }
+            }
+            self.known.insert(node, symbol);
+        }
+
+        recovered
+    }
+
+    /// Removes the symbols and equations older than the capacity.
+    fn prune(&mut self) {
+        if self.max_id < self.last_prune + self.capacity / 2 + 1 {
+            return;
+        }
+        self.last_prune = self.max_id;
+        let oldest = self.max_id.saturating_sub(self.capacity);
+
+        self.known.retain(|node, _| node.age() >= oldest);
+        self.equations
+            .retain(|_, eq| eq.unknown.iter().all(|node| node.age() >= oldest));
+        self.index.retain(|node, _| node.age() >= oldest);
+        self.precoded.retain(|first_id| *first_id >= oldest);
+    }
+}
+
+impl FecDecoderScheme for LtDecoder {
+    fn recv_ss(&mut self, pkt: &Packet) -> Result<Vec<Packet>> {
+        let &id = pkt
+            .source_metadata::<LtSSID>()
+            .ok_or(Error::FecWrongMetadata)?;
+        self.max_id = self.max_id.max(id);
+        self.prune();
+
+        let mut recovered = self.add_known(Node::Source(id), to_symbol(&pkt.data), pkt.id);
+        recovered.retain(|rec| rec.id != id);
+        Ok(recovered)
+    }
+
+    fn recv_rs(&mut self, pkt: &Packet) -> Result<Vec<Packet>> {
+        let info = pkt
+            .repair_metadata::<LtRepairInfo>()
+            .cloned()
+            .ok_or(Error::FecWrongMetadata)?;
+        if info.first_id + self.capacity < self.max_id {
+            return Err(Error::TooOldEquation);
+        }
+        self.max_id = self
+            .max_id
+            .max((info.first_id + info.nb_symbols as u64).saturating_sub(1));
+        self.prune();
+
+        // Repair symbols have their own IDs: count the recovery delay from the next source symbol.
+        let from = self.max_id + 1;
+        let mut recovered = Vec::new();
+
+        // Add the precode constraints of the block: each parity plus its sources equals zero.
+        if info.precode > 0 && self.precoded.insert(info.first_id) {
+            for (parity, sources) in precode_graph(info.first_id, info.nb_symbols, info.precode)
+                .into_iter()
+                .enumerate()
+            {
+                let mut nodes: Vec<Node> = sources
+                    .iter()
+                    .map(|&s| Node::Source(info.first_id + s as u64))
+                    .collect();
+                nodes.push(Node::Parity(info.first_id, parity));
+                recovered.extend(self.add_equation(nodes, Vec::new(), from));
+            }
+        }
+
+        let nodes: Vec<Node> = self
+            .soliton
+            .neighbours(info.seed, info.nb_symbols + info.precode)
+            .into_iter()
+            .map(|index| Node::from_index(info.first_id, info.nb_symbols, index))
+            .collect();
+        if nodes.iter().all(|node| self.known.contains_key(node)) && recovered.is_empty() {
+            return Err(Error::UnusedRepair);
+        }
+        recovered.extend(self.add_equation(nodes, pkt.data.clone(), from));
+
+        Ok(recovered)
+    }
+
+    fn stats(&self) -> FecStats {
+        vec![(
+            "pending-equations".to_string(),
+            self.get_nb_equations() as u64,
+        )]
+    }
+
+    fn name(&self) -> String {
+        format!("lt_{}", self.capacity)
+    }
+}
+
+/// Builds a LT encoder and decoder from the parameters:
+/// - `variant`: `block` (default) or `window`, any other value is an error;
+/// - `block`: number of source symbols of a block, or of the window (default: 50);
+/// - `overhead`: number of encoding symbols per source symbol (default: 0.25);
+/// - `precode`: number of precode parities per block, only for the `block` variant (default: 0);
+/// - `c`, `delta`: parameters of the robust soliton distribution (default: 0.1 and 0.5);
+/// - `capacity`: maximum number of source symbols stored by the decoder (default: 20 times the block).
+pub fn from_params(params: &FecParams) -> Result<(EncoderNode, DecoderNode)> {
+    let variant = LtVariant::try_from(params.get_or("variant", "block".to_string())?.as_str())?;
+    let k: usize = params.get_or("block", 50)?;
+    let precode: usize = params.get_or("precode", 0)?;
+    if k == 0 {
+        return Err(Error::FecEncoder("Empty LT block".to_string()));
+    }
+    if precode > 0 && variant == LtVariant::Window {
+        return Err(Error::FecEncoder(
+            "The LT precode is only available for the block variant".to_string(),
+        ));
+    }
+    let (c, delta) = (params.get_or("c", 0.1)?, params.get_or("delta", 0.5)?);
+
+    let mut encoder = LtEncoder::new(variant, k, params.get_or("overhead", 0.25)?, precode);
+    encoder.set_soliton(c, delta);
+    let encoder = EncoderNode::new(Box::new(encoder));
+
+    let mut decoder = LtDecoder::new(params.get_or("capacity", k * 20)?);
+    decoder.set_soliton(c, delta);
+    let decoder = DecoderNode::new(Box::new(decoder), None);
+
+    Ok((encoder, decoder))
+}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
@@ -5,20 +7,69 @@ use crate::Error;
 use crate::Packet;
 use crate::Result;
 use std::fmt::Debug;
+use std::time::Duration;
 
+use super::FecDecoderScheme;
+use super::FecEncoderScheme;
 use super::FecMetadata;
+use super::FecParams;
 use super::FecRepairMetadata;
 use super::FecSourceMetadata;
+use crate::node::decoder::{Decoder as DecoderNode, DecoderFeedback, FeedbackReport, LossEncoding};
+use crate::node::encoder::Encoder as EncoderNode;
 
 pub type MaelstromSSID = u64;
 
+/// Flag set in the ID of the repair packets, so that they never collide with the source symbol IDs.
+pub const REPAIR_ID_FLAG: u64 = 1 << 63;
+
+/// ID of the `index`-th repair packet generated by an encoder.
+pub fn repair_id(index: u64) -> u64 {
+    REPAIR_ID_FLAG | index
+}
+
 #[derive(Clone, Debug)]
 /// Maelstrom repair FEC information.
 pub struct MaelstromRepairInfo {
     /// List of source symbols protected by this repair symbol.
     ssid: Vec<MaelstromSSID>,
+
+    /// XOR of the payload lengths of the protected source symbols.
+    length: u64,
+
+    /// Layer of the encoder that generated this repair symbol.
+    layer: usize,
+}
+
+impl MaelstromRepairInfo {
+    pub fn new(ssid: Vec<MaelstromSSID>, length: u64, layer: usize) -> Self {
+        Self {
+            ssid,
+            length,
+            layer,
+        }
+    }
+}
+
+/// XOR repair packet of the given layer protecting the given source packets.
+pub fn xor_repair<'a>(
+    pkts: impl Iterator<Item = &'a Packet> + Clone,
+    id: u64,
+    layer: usize,
+) -> Packet {
+    let (data, length) = pkts.clone().xor();
+    let repair_info = MaelstromRepairInfo::new(pkts.map(|pkt| pkt.id).collect(), length, layer);
+    Packet {
+        id,
+        fec: Some(FecMetadata::Repair(FecRepairMetadata::new(repair_info))),
+        recovered: None,
+        data,
+        ..Default::default()
+    }
 }
 
+/// Maelstrom encoder. Each bin accumulates a running XOR of its source symbols,
+/// so that the encoder does not store the source symbols and costs O(layers) per packet.
 pub struct MaelstromEncoder {
     /// Current source symbol ID.
     ssid: u64,
@@ -27,66 +78,180 @@ pub struct MaelstromEncoder {
     /// E.g., an interleave of 100 means that 100 bins are used.
     interleaves: Vec<Vec<Bin>>,
 
-    /// All packets that are protected and still in scope.
-    pkts: HashSet<Packet>,
+    /// Virtual time after which a partially filled bin is flushed, counted from its first source symbol.
+    flush_timeout: Option<Duration>,
 
-    /// Maximum number of source symbols.
-    max_wnd: usize,
+    /// Number of source symbols protected after the first one of a partially filled bin before it is flushed.
+    flush_inactivity: Option<u64>,
+
+    /// Whether the partially filled bins are flushed at the end of the stream.
+    flush_end: bool,
+
+    /// Number of partial repair symbols flushed after a timeout.
+    nb_timeout_rs: u64,
+
+    /// Number of partial repair symbols flushed after inactivity.
+    nb_inactivity_rs: u64,
+
+    /// Number of partial repair symbols flushed at the end of the stream.
+    nb_end_rs: u64,
+
+    /// Whether each layer protects the new source symbols.
+    enabled: Vec<bool>,
+
+    /// Adaptation of the windows and layers to the feedback of the decoder, if any.
+    adaptation: Option<MaelstromAdaptation>,
 }
 
 impl Debug for MaelstromEncoder {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "maelstrom_{}_{}",
-            self.interleaves
-                .iter()
-                .map(|layer| format!("{}", layer.len()))
-                .collect::<Vec<String>>()
-                .join("_"),
-            self.max_wnd,
-        )
+        // The adaptive encoder is named after its configured windows.
+        let (prefix, windows) = match self.adaptation.as_ref() {
+            Some(adaptation) => ("maelstrom_adaptive", adaptation.base_windows.clone()),
+            None => ("maelstrom", self.get_windows()),
+        };
+        if windows.windows(2).all(|w| w[0] == w[1]) {
+            write!(
+                f,
+                "{}_{}_{}",
+                prefix,
+                self.interleaves
+                    .iter()
+                    .map(|layer| format!("{}", layer.len()))
+                    .collect::<Vec<String>>()
+                    .join("_"),
+                windows.first().unwrap_or(&0),
+            )
+        } else {
+            write!(
+                f,
+                "{}_{}",
+                prefix,
+                self.interleaves
+                    .iter()
+                    .zip(windows)
+                    .map(|(layer, window)| format!("{}x{}", layer.len(), window))
+                    .collect::<Vec<String>>()
+                    .join("_"),
+            )
+        }
     }
 }
 
 impl MaelstromEncoder {
     pub fn new(window: usize, interleaves_values: &[u64]) -> Self {
-        let mut interleaves = Vec::with_capacity(interleaves_values.len());
-        for interleave in interleaves_values.iter() {
-            let bins = (0..*interleave).map(|_| Bin::new(window)).collect();
+        let layers: Vec<(u64, usize)> = interleaves_values.iter().map(|&i| (i, window)).collect();
+        Self::new_with_layers(&layers)
+    }
+
+    /// Encoder with a window per layer, given as (interleave, window) pairs.
+    pub fn new_with_layers(layers: &[(u64, usize)]) -> Self {
+        let mut interleaves = Vec::with_capacity(layers.len());
+        for (layer, &(interleave, window)) in layers.iter().enumerate() {
+            let bins = (0..interleave).map(|_| Bin::new(window, layer)).collect();
             interleaves.push(bins);
         }
         Self {
             ssid: 0,
             interleaves,
-            pkts: HashSet::new(),
-            max_wnd: window,
+            flush_timeout: None,
+            flush_inactivity: None,
+            flush_end: false,
+            nb_timeout_rs: 0,
+            nb_inactivity_rs: 0,
+            nb_end_rs: 0,
+            enabled: vec![true; layers.len()],
+            adaptation: None,
         }
     }
 
-    /// Protects a new packet.
-    pub fn protect_symbol(&mut self, pkt: &mut Packet) -> Result<()> {
-        pkt.add_fec_metadata(super::FecMetadata::Source(FecSourceMetadata::Maelstrom(
-            self.ssid,
-        )))?;
-
-        // Remove old packets from the window.
-        let id_to_remove = self.ssid.saturating_sub(self.max_wnd as u64);
-        // Remove expired packets from the hashmap using SSID.
-        self.pkts = self
-            .pkts
-            .drain()
-            .filter(|pkt| pkt.id > id_to_remove)
-            .collect();
+    /// Adapts the windows and the enabled layers to the feedback of the decoder.
+    /// Starts with the first layer only and the configured windows.
+    pub fn set_adaptive(&mut self, alpha: f64, beta: f64) {
+        let windows = self.interleaves.iter().map(|layer| layer[0].window_size).collect();
+        self.adaptation = Some(MaelstromAdaptation::new(alpha, beta, windows));
+        self.enabled.iter_mut().skip(1).for_each(|enabled| *enabled = false);
+    }
+
+    /// Whether each layer protects the new source symbols.
+    pub fn get_enabled_layers(&self) -> &[bool] {
+        &self.enabled
+    }
+
+    /// Current window of each layer.
+    pub fn get_windows(&self) -> Vec<usize> {
+        self.interleaves.iter().map(|layer| layer[0].window_size).collect()
+    }
+
+    /// Flushes the partially filled bins after `timeout` of virtual time since their first source symbol.
+    pub fn set_flush_timeout(&mut self, timeout: Option<Duration>) {
+        self.flush_timeout = timeout;
+    }
+
+    /// Flushes the partially filled bins that sent no repair symbol for `nb_symbols` source symbols
+    /// after their first one, e.g., the bins of the deep layers when the source is slow or silent.
+    pub fn set_flush_inactivity(&mut self, nb_symbols: Option<u64>) {
+        self.flush_inactivity = nb_symbols;
+    }
+
+    /// Flushes the partially filled bins at the end of the stream.
+    pub fn set_flush_end(&mut self, flush_end: bool) {
+        self.flush_end = flush_end;
+    }
+
+    /// Number of partial repair symbols flushed, whatever the reason.
+    pub fn get_nb_flushed_rs(&self) -> u64 {
+        self.nb_timeout_rs + self.nb_inactivity_rs + self.nb_end_rs
+    }
+
+    /// Whether more than `flush_inactivity` source symbols were protected after the first one of the bin.
+    fn is_inactive(&self, bin: &Bin) -> bool {
+        self.flush_inactivity
+            .is_some_and(|n| bin.symbols.first().is_some_and(|&first| self.ssid - 1 - first > n))
+    }
+
+    /// Repair symbols of all the bins satisfying the predicate.
+    fn generate_rs_if(&mut self, mut predicate: impl FnMut(&Self, &Bin) -> bool) -> Vec<Packet> {
+        let mut nb_rs = self.get_nb_rs();
+        let mut out = Vec::new();
+        for layer in 0..self.interleaves.len() {
+            for bin in 0..self.interleaves[layer].len() {
+                if predicate(self, &self.interleaves[layer][bin]) {
+                    out.push(self.interleaves[layer][bin].generate_rs(repair_id(nb_rs)));
+                    nb_rs += 1;
+                }
+            }
+        }
+        out
+    }
+
+    /// Get the total number of repair symbols generated.
+    pub fn get_nb_rs(&self) -> u64 {
+        self.get_nb_rs_per_layer().iter().sum()
+    }
+
+    /// Get the number of repair symbols generated by each layer.
+    pub fn get_nb_rs_per_layer(&self) -> Vec<u64> {
+        self.interleaves
+            .iter()
+            .map(|layer| layer.iter().map(|bin| bin.get_nb_rs()).sum())
+            .collect()
+    }
+}
 
-        // Add the packet to the list of packets that are protected.
-        self.pkts.insert(pkt.clone());
+impl FecEncoderScheme for MaelstromEncoder {
+    /// Protects a new packet.
+    fn protect_symbol(&mut self, pkt: &mut Packet) -> Result<()> {
+        pkt.add_fec_metadata(super::FecMetadata::Source(FecSourceMetadata::new::<
+            MaelstromSSID,
+        >(self.ssid)))?;
 
-        // Add the packet to the correct bin of each layer.
-        self.interleaves.iter_mut().for_each(|layer| {
+        // Add the packet to the correct bin of each enabled layer.
+        let layers = self.interleaves.iter_mut().zip(&self.enabled);
+        layers.filter(|(_, &enabled)| enabled).for_each(|(layer, _)| {
             let n = layer.len();
             let bin = &mut layer[self.ssid as usize % n];
-            bin.symbols.insert(self.ssid);
+            bin.add_symbol(self.ssid, &pkt.data, pkt.time);
         });
 
         self.ssid += 1;
@@ -94,79 +259,228 @@ impl MaelstromEncoder {
     }
 
     /// Whether at least a bin from a layer should generate a repair symbol.
-    pub fn should_generate_rs(&self) -> bool {
-        self.interleaves
-            .iter()
-            .any(|layer| layer.iter().any(|bin| bin.should_generate_rs()))
+    fn should_generate_rs(&mut self) -> bool {
+        self.interleaves.iter().any(|layer| {
+            layer
+                .iter()
+                .any(|bin| bin.should_generate_rs() || self.is_inactive(bin))
+        })
     }
 
     /// Generate as many repair symbols as needed by calling every bin from every layer.
-    pub fn generate_rs(&mut self) -> Result<Vec<Packet>> {
-        Ok(self
-            .interleaves
-            .iter_mut()
-            .flat_map(|layer| {
-                layer
-                    .iter_mut()
-                    .filter_map(|bin| bin.generate_rs(&self.pkts))
-            })
-            .collect())
+    fn generate_rs(&mut self) -> Result<Vec<Packet>> {
+        let mut out = self.generate_rs_if(|_, bin| bin.should_generate_rs());
+        let inactive = self.generate_rs_if(|encoder, bin| encoder.is_inactive(bin));
+        self.nb_inactivity_rs += inactive.len() as u64;
+        out.extend(inactive);
+        Ok(out)
     }
 
-    /// Get the total number of repair symbols generated.
-    pub fn get_nb_rs(&self) -> u64 {
-        self.interleaves
+    fn on_timer(&mut self, now: Duration) -> Result<Vec<Packet>> {
+        let Some(timeout) = self.flush_timeout else {
+            return Ok(Vec::new());
+        };
+        let out = self.generate_rs_if(|_, bin| {
+            !bin.symbols.is_empty() && now.saturating_sub(bin.first_time) >= timeout
+        });
+        self.nb_timeout_rs += out.len() as u64;
+        Ok(out)
+    }
+
+    fn flush(&mut self) -> Result<Vec<Packet>> {
+        if !self.flush_end {
+            return Ok(Vec::new());
+        }
+        let out = self.generate_rs_if(|_, bin| !bin.symbols.is_empty());
+        self.nb_end_rs += out.len() as u64;
+        Ok(out)
+    }
+
+    fn recv_feedback(&mut self, feedback: &FeedbackReport) {
+        let Some(adaptation) = self.adaptation.as_mut() else {
+            return;
+        };
+        if feedback.get_nb_elems() == 0 {
+            return;
+        }
+        let loss = feedback.get_nb_lost() as f64 / feedback.get_nb_elems() as f64;
+        adaptation.update(loss, feedback.get_max_burst() as f64);
+
+        // Windows keep the ratios of the configured windows.
+        let windows = adaptation.windows();
+        for (layer, window) in self.interleaves.iter_mut().zip(windows) {
+            layer.iter_mut().for_each(|bin| bin.window_size = window);
+        }
+
+        // A layer is only useful if the bursts are longer than the interleave of the layer below.
+        let burst = adaptation.burst_estimation;
+        for layer in 1..self.interleaves.len() {
+            let enabled = burst > self.interleaves[layer - 1].len() as f64;
+            if self.enabled[layer] && !enabled {
+                // The source symbols of the partially filled bins stay unprotected by this layer.
+                self.interleaves[layer].iter_mut().for_each(|bin| bin.reset());
+            }
+            self.enabled[layer] = enabled;
+        }
+        info!(
+            "Maelstrom adaptation: windows {:?}, layers {:?}",
+            self.get_windows(),
+            self.enabled
+        );
+    }
+
+    fn stats(&self) -> super::FecStats {
+        let mut stats = vec![
+            ("repairs".to_string(), self.get_nb_rs()),
+            ("timeout-flushes".to_string(), self.nb_timeout_rs),
+            ("inactivity-flushes".to_string(), self.nb_inactivity_rs),
+            ("end-flushes".to_string(), self.nb_end_rs),
+        ];
+        if let Some(adaptation) = self.adaptation.as_ref() {
+            stats.push(("adaptations".to_string(), adaptation.nb_updates));
+            stats.push(("window".to_string(), self.get_windows()[0] as u64));
+            let nb_enabled = self.enabled.iter().filter(|&&e| e).count();
+            stats.push(("enabled-layers".to_string(), nb_enabled as u64));
+        }
+        stats.extend(
+            self.get_nb_rs_per_layer()
+                .into_iter()
+                .enumerate()
+                .map(|(layer, nb_rs)| (format!("layer{}-repairs", layer), nb_rs)),
+        );
+        stats
+    }
+
+    fn name(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// Estimation of the loss rate and of the burst length from the decoder feedback,
+/// used to scale the windows of the [`MaelstromEncoder`].
+struct MaelstromAdaptation {
+    /// Learning parameter for the moving averages.
+    alpha: f64,
+
+    /// Tweaking parameter to increase redundancy ratio.
+    beta: f64,
+
+    /// Estimated mean loss rate.
+    loss_estimation: f64,
+
+    /// Estimated length of the longest burst of losses between two feedbacks.
+    burst_estimation: f64,
+
+    /// Configured window of each layer.
+    base_windows: Vec<usize>,
+
+    /// Number of feedbacks taken into account.
+    nb_updates: u64,
+}
+
+/// Maximum factor between the adapted and the configured windows.
+const MAX_WINDOW_SCALE: f64 = 4.0;
+
+impl MaelstromAdaptation {
+    fn new(alpha: f64, beta: f64, base_windows: Vec<usize>) -> Self {
+        Self {
+            alpha,
+            beta,
+            loss_estimation: 0.0,
+            burst_estimation: 0.0,
+            base_windows,
+            nb_updates: 0,
+        }
+    }
+
+    fn update(&mut self, loss: f64, burst: f64) {
+        self.loss_estimation = self.loss_estimation * self.alpha + (1.0 - self.alpha) * loss;
+        self.burst_estimation = self.burst_estimation * self.alpha + (1.0 - self.alpha) * burst;
+        self.nb_updates += 1;
+    }
+
+    /// Windows such that the first layer sends a repair symbol every `1 / (beta * loss)` source symbols.
+    fn windows(&self) -> Vec<usize> {
+        let base = self.base_windows[0] as f64;
+        let target = 1.0 / (self.beta * self.loss_estimation).max(f64::EPSILON);
+        let scale = (target / base).min(MAX_WINDOW_SCALE);
+        self.base_windows
             .iter()
-            .flat_map(|layer| layer.iter().map(|bin| bin.get_nb_rs()))
-            .sum()
+            .map(|&window| ((window as f64 * scale).round() as usize).max(1))
+            .collect()
     }
 }
 
 /// A bin of an interleave. Contains the symbols to protect and materials to generate the repair symbols.
 struct Bin {
-    /// Stored source symbols that will be protected.
-    symbols: HashSet<MaelstromSSID>,
+    /// Source symbols added since the last repair symbol.
+    symbols: Vec<MaelstromSSID>,
+
+    /// Running XOR of the payloads of these source symbols.
+    data: Vec<u8>,
+
+    /// Running XOR of the payload lengths of these source symbols.
+    length: u64,
+
+    /// Virtual time of the first of these source symbols.
+    first_time: Duration,
 
     /// Number of repair symbols generated.
     nb_rs: u64,
 
     /// Maximum size of the window, i.e., number of source symbols required to generate a repair symbol.
     window_size: usize,
+
+    /// Index of the layer of the bin.
+    layer: usize,
 }
 
 impl Bin {
-    fn new(window_size: usize) -> Self {
+    fn new(window_size: usize, layer: usize) -> Self {
         Self {
-            symbols: HashSet::new(),
+            symbols: Vec::with_capacity(window_size),
+            data: Vec::new(),
+            length: 0,
+            first_time: Duration::ZERO,
             nb_rs: 0,
             window_size,
+            layer,
         }
     }
 
-    fn generate_rs(&mut self, all_pkts: &HashSet<Packet>) -> Option<Packet> {
-        if self.symbols.len() >= self.window_size {
-            let mut pkt = all_pkts
-                .iter()
-                .filter(|pkt| self.symbols.contains(&pkt.id))
-                .xor();
+    fn add_symbol(&mut self, ssid: MaelstromSSID, data: &[u8], time: Duration) {
+        if self.symbols.is_empty() {
+            self.first_time = time;
+        }
+        self.symbols.push(ssid);
+        xor_into(&mut self.data, data);
+        self.length ^= data.len() as u64;
+    }
 
-            // Add FEC repair state for the generated repair packet.
-            let repair_info = MaelstromRepairInfo {
-                ssid: self.symbols.iter().copied().collect(),
-            };
-            pkt.add_fec_metadata(super::FecMetadata::Repair(
-                super::FecRepairMetadata::Maelstrom(repair_info),
-            ))
-            .ok();
+    /// Drops the source symbols of the bin without generating a repair symbol.
+    fn reset(&mut self) {
+        self.symbols.clear();
+        self.data.clear();
+        self.length = 0;
+    }
 
-            // Reset state.
-            self.symbols = HashSet::new();
-            self.nb_rs += 1;
+    /// Repair symbol protecting the source symbols of the bin, even if it is not full.
+    fn generate_rs(&mut self, id: u64) -> Packet {
+        let symbols = std::mem::take(&mut self.symbols);
+        let repair_info = MaelstromRepairInfo::new(symbols, self.length, self.layer);
+        let pkt = Packet {
+            id,
+            fec: Some(FecMetadata::Repair(FecRepairMetadata::new(repair_info))),
+            recovered: None,
+            data: std::mem::take(&mut self.data),
+            ..Default::default()
+        };
 
-            Some(pkt)
-        } else {
-            None
-        }
+        // Reset state.
+        self.length = 0;
+        self.nb_rs += 1;
+
+        pkt
     }
 
     fn should_generate_rs(&self) -> bool {
@@ -178,129 +492,306 @@ impl Bin {
     }
 }
 
+/// `dst ^= src`, byte by byte. `dst` is padded with zeros if it is shorter than `src`.
+fn xor_into(dst: &mut Vec<u8>, src: &[u8]) {
+    if dst.len() < src.len() {
+        dst.resize(src.len(), 0);
+    }
+    dst.iter_mut().zip(src).for_each(|(d, s)| *d ^= s);
+}
+
 pub trait XorPackets {
-    fn xor(self) -> Packet;
+    /// XOR of the payloads, padded with zeros to the longest one, and XOR of their lengths.
+    fn xor(self) -> (Vec<u8>, u64);
 }
 
 impl<'a, I> XorPackets for I
 where
     I: Iterator<Item = &'a Packet>,
 {
-    fn xor(self) -> Packet {
-        let data = self.fold(0, |cur, pkt| {
-            cur ^ u64::from_be_bytes(pkt.data.clone().try_into().unwrap())
-        });
-
-        Packet {
-            id: data,
-            fec: None,
-            recovered: None,
-            data: data.to_be_bytes().to_vec(),
-        }
+    fn xor(self) -> (Vec<u8>, u64) {
+        self.fold((Vec::new(), 0), |(mut data, length), pkt| {
+            xor_into(&mut data, &pkt.data);
+            (data, length ^ pkt.data.len() as u64)
+        })
     }
 }
 
 pub struct MaelstromDecoder {
-    /// All equations.
+    /// Pending equations, i.e., with at least two missing source symbols.
     equations: HashMap<u64, Equation>,
 
+    /// Pending equations referencing each missing source symbol.
+    index: BTreeMap<MaelstromSSID, Vec<u64>>,
+
+    /// Pending equations ordered by their smallest SSID, to evict them from the window.
+    expiry: BTreeSet<(MaelstromSSID, u64)>,
+
     /// Max SSID received. Used to prune too old packets.
     max_ssid: u64,
 
     /// Current equation ID.
     eq_id: u64,
 
-    /// All source symbols received.
-    pkts: HashMap<u64, Packet>,
+    /// Source symbols received or recovered, ordered by SSID.
+    pkts: BTreeMap<u64, Packet>,
 
     /// Maximum number of source symbols stored.
     capacity: usize,
+
+    /// Maximum number of source symbols stored at the same time.
+    max_stored: usize,
+
+    /// Maximum number of pending equations at the same time.
+    max_equations: usize,
+
+    /// Number of pending equations evicted from the window before being solved.
+    nb_evicted_equations: u64,
+
+    /// Number of source symbols recovered by the repair symbols of each layer.
+    nb_recovered_per_layer: Vec<u64>,
+
+    /// Whether a Gaussian elimination over the pending equations complements the peeling decoder.
+    gaussian_elimination: bool,
+
+    /// Whether the pending equations changed since the last Gaussian elimination.
+    dirty: bool,
+
+    /// Number of source symbols recovered during Gaussian eliminations, i.e., that peeling had not recovered yet.
+    nb_extra_recovered: u64,
 }
 
 impl MaelstromDecoder {
     pub fn new(capacity: usize) -> Self {
         Self {
             equations: HashMap::new(),
+            index: BTreeMap::new(),
+            expiry: BTreeSet::new(),
             max_ssid: 0,
             eq_id: 0,
-            pkts: HashMap::new(),
+            pkts: BTreeMap::new(),
             capacity,
+            max_stored: 0,
+            max_equations: 0,
+            nb_evicted_equations: 0,
+            nb_recovered_per_layer: Vec::new(),
+            gaussian_elimination: false,
+            dirty: false,
+            nb_extra_recovered: 0,
         }
     }
 
-    pub fn recv_ss(&mut self, pkt: &Packet) -> Result<Vec<Packet>> {
-        if let Some(FecMetadata::Source(FecSourceMetadata::Maelstrom(mut metadata))) = pkt.fec {
-            let id_to_remove = metadata.saturating_sub(self.capacity as u64);
-            let _ids_to_remove: Vec<_> = self
-                .equations
-                .values()
-                .filter(|eq| eq.get_min_ssid().unwrap() >= id_to_remove)
-                .map(|eq| eq.id)
-                .collect();
-            // for idx in ids_to_remove {
-            //     self.equations.remove(&idx);
-            // }
-
-            // Remove expired packets from the hashmap using SSID.
-            // self.pkts = self
-            //     .pkts
-            //     .drain()
-            //     .filter(|(id, _)| *id > id_to_remove)
-            //     .collect();
+    /// Complements the peeling decoder with a Gaussian elimination over GF(2) of the pending equations.
+    pub fn set_gaussian_elimination(&mut self, enabled: bool) {
+        self.gaussian_elimination = enabled;
+    }
 
-            // Add to the list of received packets.
-            self.pkts.insert(pkt.id, pkt.clone());
-            self.max_ssid = self.max_ssid.max(metadata);
+    /// Number of source symbols recovered during Gaussian eliminations, including the ones peeled in cascade.
+    pub fn get_nb_extra_recovered(&self) -> u64 {
+        self.nb_extra_recovered
+    }
 
-            // Add the ID to the existing equations. No effect on equations that did not need it.
-            let mut ids_to_remove = HashSet::new();
-            let mut recovered = HashSet::new();
-            loop {
-                // Add the symbol to all equations.
-                for equation in self.equations.values_mut() {
-                    if equation.add_symbol(metadata) == DecoderAction::Redundant {
-                        ids_to_remove.insert(equation.id);
-                    }
-                }
+    /// Number of equations waiting for missing source symbols.
+    pub fn get_nb_equations(&self) -> usize {
+        self.equations.len()
+    }
+
+    /// Number of source symbols currently stored.
+    pub fn get_nb_stored_symbols(&self) -> usize {
+        self.pkts.len()
+    }
+
+    pub fn get_max_stored_symbols(&self) -> usize {
+        self.max_stored
+    }
+
+    pub fn get_max_equations(&self) -> usize {
+        self.max_equations
+    }
+
+    pub fn get_nb_evicted_equations(&self) -> u64 {
+        self.nb_evicted_equations
+    }
+
+    /// Number of source symbols recovered by the repair symbols of each layer.
+    pub fn get_nb_recovered_per_layer(&self) -> &[u64] {
+        &self.nb_recovered_per_layer
+    }
+
+    fn count_recovered(&mut self, layer: usize) {
+        if self.nb_recovered_per_layer.len() <= layer {
+            self.nb_recovered_per_layer.resize(layer + 1, 0);
+        }
+        self.nb_recovered_per_layer[layer] += 1;
+    }
 
-                // Solve an equation thanks to this symbol. Restart until no new source symbol can be recovered.
-                let mut at_least_one = false;
-                for equation in self.equations.values_mut() {
-                    if equation.action() == DecoderAction::Recover
-                        && !ids_to_remove.contains(&equation.id)
-                    {
-                        let local = equation.recover(&self.pkts);
-                        if let Some(mut rec) = local {
-                            rec.recovered = Some(pkt.id.saturating_sub(rec.id));
-                            metadata = u64::from_be_bytes(rec.data.clone().try_into().unwrap());
-                            recovered.insert(rec.clone());
-                            at_least_one = true;
-                            self.pkts.insert(rec.id, rec.clone());
-                            // println!("Recover source symbol: {} for equation {} but received is: {}", metadata, equation.id, pkt.id);
-                            break;
+    /// Removes the source symbols and the pending equations older than the capacity.
+    fn prune(&mut self) {
+        let id_to_remove = self.max_ssid.saturating_sub(self.capacity as u64);
+        self.pkts = self.pkts.split_off(&id_to_remove);
+        self.index = self.index.split_off(&id_to_remove);
+
+        while let Some(&(min_ssid, id)) = self.expiry.first() {
+            if min_ssid >= id_to_remove {
+                break;
+            }
+            self.expiry.pop_first();
+            if self.equations.remove(&id).is_some() {
+                self.nb_evicted_equations += 1;
+            }
+        }
+    }
+
+    fn update_memory_stats(&mut self) {
+        self.max_stored = self.max_stored.max(self.pkts.len());
+        self.max_equations = self.max_equations.max(self.equations.len());
+    }
+
+    /// Adds a received or recovered source symbol to the equations referencing it, and recovers
+    /// the source symbols that can be recovered in cascade.
+    fn add_symbol(&mut self, pkt: &Packet) -> Vec<Packet> {
+        let mut recovered = Vec::new();
+        let mut symbols = vec![pkt.id];
+        while let Some(ssid) = symbols.pop() {
+            let (Some(eq_ids), Some(symbol)) = (self.index.remove(&ssid), self.pkts.get(&ssid))
+            else {
+                continue;
+            };
+            let symbol = symbol.clone();
+            for eq_id in eq_ids {
+                // The equation may have been solved or evicted in the meantime.
+                let Some(equation) = self.equations.get_mut(&eq_id) else {
+                    continue;
+                };
+                match equation.add_symbol(&symbol) {
+                    DecoderAction::Missing => self.dirty = true,
+                    DecoderAction::Redundant => {
+                        self.equations.remove(&eq_id);
+                    }
+                    DecoderAction::Recover => {
+                        let mut rec = equation.recover();
+                        let layer = equation.layer;
+                        self.equations.remove(&eq_id);
+                        if self.pkts.contains_key(&rec.id) {
+                            continue;
                         }
+                        self.count_recovered(layer);
+                        rec.recovered = Some(pkt.id.saturating_sub(rec.id));
+                        symbols.push(rec.id);
+                        self.pkts.insert(rec.id, rec.clone());
+                        recovered.push(rec);
                     }
                 }
+            }
+        }
+        recovered
+    }
+
+    /// Recovers the source symbols determined by the pending equations together, which peeling may miss
+    /// (e.g., `A ^ B ^ C` and `A ^ B`), with a Gaussian elimination over GF(2).
+    fn eliminate(&mut self) -> Vec<Packet> {
+        if !self.gaussian_elimination || !self.dirty || self.equations.len() < 2 {
+            return Vec::new();
+        }
+        self.dirty = false;
+
+        let unknowns: Vec<MaelstromSSID> = self
+            .equations
+            .values()
+            .flat_map(|eq| eq.missing.iter().copied())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let column: HashMap<MaelstromSSID, usize> =
+            unknowns.iter().enumerate().map(|(i, ssid)| (*ssid, i)).collect();
+        let nb_words = unknowns.len().div_ceil(64);
+        // Coefficients, data, length and layer of each row. Each row keeps the layer of its pivot
+        // equation, credited for the source symbol it recovers.
+        type Row = (Vec<u64>, Vec<u8>, u64, usize);
+        let mut rows: Vec<Row> = self
+            .equations
+            .values()
+            .map(|eq| {
+                let mut coefs = vec![0u64; nb_words];
+                for ssid in eq.missing.iter() {
+                    coefs[column[ssid] / 64] |= 1 << (column[ssid] % 64);
+                }
+                (coefs, eq.data.clone(), eq.length, eq.layer)
+            })
+            .collect();
 
-                if !at_least_one {
-                    break;
+        // Reduced row echelon form: each pivot column is zero in all the other rows.
+        let mut nb_pivots = 0;
+        for col in 0..unknowns.len() {
+            let has = |row: &Row| row.0[col / 64] & (1 << (col % 64)) != 0;
+            let Some(pivot) = (nb_pivots..rows.len()).find(|&r| has(&rows[r])) else {
+                continue;
+            };
+            rows.swap(nb_pivots, pivot);
+            let (coefs, data, length, _) = rows[nb_pivots].clone();
+            for (r, row) in rows.iter_mut().enumerate() {
+                if r != nb_pivots && has(row) {
+                    row.0.iter_mut().zip(&coefs).for_each(|(c, p)| *c ^= p);
+                    xor_into(&mut row.1, &data);
+                    row.2 ^= length;
                 }
             }
+            nb_pivots += 1;
+        }
 
-            // Clean expired equations.
-            for id in ids_to_remove {
-                self.equations.remove(&id);
+        // A row with a single unknown left determines it.
+        let mut recovered = Vec::new();
+        for (coefs, mut data, length, layer) in rows.into_iter().take(nb_pivots) {
+            if coefs.iter().map(|c| c.count_ones()).sum::<u32>() != 1 {
+                continue;
+            }
+            let col = coefs.iter().enumerate().find(|(_, c)| **c != 0).unwrap();
+            let ssid = unknowns[col.0 * 64 + col.1.trailing_zeros() as usize];
+            if self.pkts.contains_key(&ssid) {
+                continue;
             }
+            data.resize(length as usize, 0);
+            let rec = Packet {
+                id: ssid,
+                fec: Some(FecMetadata::Source(FecSourceMetadata::new::<MaelstromSSID>(ssid))),
+                recovered: Some((self.max_ssid + 1).saturating_sub(ssid)),
+                data,
+                ..Default::default()
+            };
+            self.pkts.insert(ssid, rec.clone());
+            self.count_recovered(layer);
+            recovered.extend(self.add_symbol(&rec));
+            recovered.push(rec);
+        }
+        self.nb_extra_recovered += recovered.len() as u64;
+        recovered
+    }
+}
+
+impl FecDecoderScheme for MaelstromDecoder {
+    fn recv_ss(&mut self, pkt: &Packet) -> Result<Vec<Packet>> {
+        if let Some(&metadata) = pkt.source_metadata::<MaelstromSSID>() {
+            // Add to the list of received packets.
+            self.pkts.insert(pkt.id, pkt.clone());
+            self.max_ssid = self.max_ssid.max(metadata);
+
+            // Remove expired packets and equations using SSID.
+            self.prune();
+
+            // Only the equations referencing this symbol are updated.
+            let mut recovered = self.add_symbol(pkt);
+            recovered.extend(self.eliminate());
+            self.update_memory_stats();
 
-            Ok(recovered.into_iter().collect())
+            Ok(recovered)
         } else {
             Err(Error::FecWrongMetadata)
         }
     }
 
-    pub fn recv_rs(&mut self, pkt: &Packet) -> Result<Vec<Packet>> {
-        if let Some(FecMetadata::Repair(FecRepairMetadata::Maelstrom(repair))) = pkt.fec.as_ref() {
-            let mut recovered = HashSet::new();
+    fn recv_rs(&mut self, pkt: &Packet) -> Result<Vec<Packet>> {
+        if let Some(repair) = pkt.repair_metadata::<MaelstromRepairInfo>() {
+            let mut recovered = Vec::new();
 
             // Maybe the equation is too old (i.e., source symbols are already removes from the window).
             // In that case, we do not use the equation.
@@ -312,31 +803,155 @@ impl MaelstromDecoder {
             }
 
             // Add a new equation from this repair symbol.
-            let mut new_equation = Equation::new(pkt.clone(), self.eq_id)?;
+            let mut new_equation = Equation::new(pkt, self.eq_id)?;
             self.eq_id += 1;
-            new_equation.populate(&self.pkts);
-            match new_equation.action() {
+            match new_equation.populate(&self.pkts) {
                 DecoderAction::Redundant => (), // Useless repair symbol.
                 DecoderAction::Missing => {
                     // Not enough source symbols to recover a packet.
                     // This does not change the state of the other equations as well.
-                    self.equations.insert(self.eq_id, new_equation);
+                    for ssid in new_equation.missing.iter() {
+                        self.index.entry(*ssid).or_default().push(new_equation.id);
+                    }
+                    self.expiry.insert((new_equation.min_ssid, new_equation.id));
+                    self.equations.insert(new_equation.id, new_equation);
+                    self.update_memory_stats();
+                    self.dirty = true;
+                    recovered.extend(self.eliminate());
                 }
                 DecoderAction::Recover => {
                     // Resolve the equation but do not add it to the system because we solve it directly.
-                    let local = new_equation.recover(&self.pkts);
-                    if let Some(mut rec) = local {
-                        rec.recovered = Some(pkt.id.saturating_sub(rec.id));
-                        recovered.extend(self.recv_ss(&rec)?);
-                        recovered.insert(rec);
-                    }
+                    let mut rec = new_equation.recover();
+                    self.count_recovered(new_equation.layer);
+                    // Repair IDs are not in the source sequence: count from the next expected source symbol.
+                    rec.recovered = Some((self.max_ssid + 1).saturating_sub(rec.id));
+                    recovered.extend(self.recv_ss(&rec)?);
+                    recovered.push(rec);
                 }
             }
-            Ok(recovered.into_iter().collect())
+            Ok(recovered)
         } else {
             Err(Error::FecWrongMetadata)
         }
     }
+
+    fn stats(&self) -> super::FecStats {
+        let mut stats = vec![
+            (
+                "pending-equations".to_string(),
+                self.get_nb_equations() as u64,
+            ),
+            (
+                "max-pending-equations".to_string(),
+                self.get_max_equations() as u64,
+            ),
+            (
+                "evicted-equations".to_string(),
+                self.get_nb_evicted_equations(),
+            ),
+            (
+                "stored-symbols".to_string(),
+                self.get_nb_stored_symbols() as u64,
+            ),
+            (
+                "max-stored-symbols".to_string(),
+                self.get_max_stored_symbols() as u64,
+            ),
+        ];
+        if self.gaussian_elimination {
+            stats.push((
+                "extra-recovered".to_string(),
+                self.get_nb_extra_recovered(),
+            ));
+        }
+        stats.extend(
+            self.nb_recovered_per_layer
+                .iter()
+                .enumerate()
+                .map(|(layer, nb)| (format!("layer{}-recovered", layer), *nb)),
+        );
+        stats
+    }
+
+    fn name(&self) -> String {
+        if self.gaussian_elimination {
+            format!("maelstrom_gauss_{}", self.capacity)
+        } else {
+            format!("maelstrom_{}", self.capacity)
+        }
+    }
+}
+
+/// Builds a Maelstrom encoder and decoder from the parameters:
+/// - `window`: number of source symbols protected by a repair symbol (default: 100);
+/// - `layering`: interleave of each layer, separated by `,` or `_`, optionally with the window of the layer
+///   after a `:`, e.g., `1:8,20:10,40:16` (default: `1,20,40`);
+/// - `capacity`: maximum number of source symbols stored by the decoder
+///   (default: twice the span of the deepest layer, and at least 20 times the window);
+/// - `flush-timeout-us`: virtual time after which a partially filled bin is flushed (default: never);
+/// - `flush-inactivity`: number of source symbols after the first one of a partially filled bin
+///   before it is flushed (default: never);
+/// - `flush-end`: whether the partially filled bins are flushed at the end of the stream (default: false);
+/// - `decoder`: `peeling` (default) or `gaussian` to complement peeling with a Gaussian elimination;
+/// - `adaptive`: whether the windows and the enabled layers follow the decoder feedback (default: false);
+/// - `alpha`, `beta`, `feedback`: moving average parameter, redundancy factor and number of source symbols
+///   between two feedbacks of the adaptive encoder (default: 0.9, 1.0 and 500);
+/// - `feedback-encoding`: loss pattern of the feedback, `bitmap` (default), `rle` or `summary`.
+pub fn from_params(params: &FecParams) -> Result<(EncoderNode, DecoderNode)> {
+    let window: usize = params.get_or("window", 100)?;
+    let layers = parse_layering(&params.get_or("layering", "1,20,40".to_string())?, window)?;
+
+    let mut encoder = MaelstromEncoder::new_with_layers(&layers);
+    encoder.set_flush_timeout(params.get("flush-timeout-us")?.map(Duration::from_micros));
+    encoder.set_flush_inactivity(params.get("flush-inactivity")?);
+    encoder.set_flush_end(params.get_or("flush-end", false)?);
+    let adaptive = params.get_or("adaptive", false)?;
+    if adaptive {
+        encoder.set_adaptive(params.get_or("alpha", 0.9)?, params.get_or("beta", 1.0)?);
+    }
+    let encoder = EncoderNode::new(Box::new(encoder));
+
+    // A repair symbol of a layer protects source symbols spanning the interleave times the window.
+    let scale = if adaptive { MAX_WINDOW_SCALE as usize } else { 1 };
+    let span = layers
+        .iter()
+        .map(|&(interleave, window)| interleave as usize * window * scale)
+        .max();
+    let capacity = params.get_or("capacity", (window * scale * 20).max(span.unwrap_or(0) * 2))?;
+    let mut decoder = MaelstromDecoder::new(capacity);
+    decoder.set_gaussian_elimination(params.get_or("decoder", "peeling".to_string())? == "gaussian");
+    let feedback = if adaptive {
+        let mut feedback = DecoderFeedback::new(params.get_or("feedback", 500)?);
+        let encoding = params.get_or("feedback-encoding", String::new())?;
+        feedback.set_encoding(LossEncoding::from(encoding.as_str()));
+        Some(feedback)
+    } else {
+        None
+    };
+    let decoder = DecoderNode::new(Box::new(decoder), feedback);
+
+    Ok((encoder, decoder))
+}
+
+/// Parses a layering such as `1,20,40` or `1:8,20:10,40:16` into (interleave, window) pairs.
+/// Layers without window use the default `window`.
+pub fn parse_layering(layering: &str, window: usize) -> Result<Vec<(u64, usize)>> {
+    layering
+        .split([',', '_'])
+        .map(|item| {
+            let wrong = || Error::FecEncoder(format!("Wrong layering: {}", item));
+            let (interleave, layer_window) = match item.split_once(':') {
+                Some((interleave, window)) => (interleave, window.parse().map_err(|_| wrong())?),
+                None => (item, window),
+            };
+            match interleave.parse() {
+                Ok(interleave) if interleave > 0 && layer_window > 0 => {
+                    Ok((interleave, layer_window))
+                }
+                _ => Err(wrong()),
+            }
+        })
+        .collect()
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -353,26 +968,34 @@ enum DecoderAction {
 
 #[derive(Debug)]
 pub struct Equation {
-    /// IDs of source symbols protected by this equation that are received.
-    recv_ssid: HashSet<MaelstromSSID>,
+    /// XOR of the repair payload and of the payloads of the received source symbols.
+    data: Vec<u8>,
+
+    /// XOR of the payload lengths of the missing source symbols.
+    length: u64,
 
-    /// Repair FEC payload
-    repair: Packet,
+    /// IDs of source symbols protected by this equation that are still missing.
+    missing: HashSet<MaelstromSSID>,
 
-    /// IDs of source symbols that are needed by this equation.
-    need_ssid: HashSet<MaelstromSSID>,
+    /// Smallest SSID protected by this equation.
+    min_ssid: MaelstromSSID,
+
+    /// Layer of the repair symbol.
+    layer: usize,
 
     /// Unique ID.
     id: u64,
 }
 
 impl Equation {
-    fn new(repair: Packet, id: u64) -> Result<Self> {
-        if let Some(FecMetadata::Repair(FecRepairMetadata::Maelstrom(fec))) = repair.fec.clone() {
+    fn new(repair: &Packet, id: u64) -> Result<Self> {
+        if let Some(fec) = repair.repair_metadata::<MaelstromRepairInfo>() {
             Ok(Self {
-                recv_ssid: HashSet::new(),
-                need_ssid: fec.ssid.iter().copied().collect(),
-                repair,
+                data: repair.data.clone(),
+                length: fec.length,
+                missing: fec.ssid.iter().copied().collect(),
+                min_ssid: fec.ssid.iter().min().copied().ok_or(Error::FecWrongMetadata)?,
+                layer: fec.layer,
                 id,
             })
         } else {
@@ -380,55 +1003,44 @@ impl Equation {
         }
     }
 
-    /// Fill all received source symbols in the equation. Returns true if all symbols have been received.
-    fn populate(&mut self, pkts: &HashMap<u64, Packet>) -> DecoderAction {
-        pkts.values().for_each(|pkt| {
-            if self.need_ssid.contains(&pkt.id) {
-                self.recv_ssid.insert(pkt.id);
-            }
-        });
+    /// Fill all received source symbols in the equation.
+    fn populate(&mut self, pkts: &BTreeMap<u64, Packet>) -> DecoderAction {
+        let received: Vec<&Packet> = self.missing.iter().filter_map(|id| pkts.get(id)).collect();
+        for pkt in received {
+            self.add_symbol(pkt);
+        }
         self.action()
     }
 
     fn action(&self) -> DecoderAction {
-        match self.need_ssid.len().saturating_sub(self.recv_ssid.len()) as u64 {
+        match self.missing.len() {
             0 => DecoderAction::Redundant,
             1 => DecoderAction::Recover,
             _ => DecoderAction::Missing,
         }
     }
 
-    /// Add a new source symbol to the bin. Returns true if the equation can be solved.
-    /// Returns false otherwise. Also returns false if the symbol was not needed by this equation.
-    fn add_symbol(&mut self, id: MaelstromSSID) -> DecoderAction {
-        if self.need_ssid.contains(&id) {
-            self.recv_ssid.insert(id);
+    /// Add a new source symbol to the equation, and XOR its payload. No effect if the symbol was not missing.
+    fn add_symbol(&mut self, pkt: &Packet) -> DecoderAction {
+        if self.missing.remove(&pkt.id) {
+            xor_into(&mut self.data, &pkt.data);
+            self.length ^= pkt.data.len() as u64;
         }
         self.action()
     }
 
-    /// Recover a lost source symbol.
-    fn recover(&mut self, pkts: &HashMap<u64, Packet>) -> Option<Packet> {
-        if self.action() == DecoderAction::Recover {
-            let mut rec = pkts
-                .values()
-                .filter(|pkt| self.need_ssid.contains(&pkt.id))
-                .chain([&self.repair].iter().copied())
-                .xor();
+    /// Recover the last missing source symbol. The equation must be in the [`DecoderAction::Recover`] state.
+    fn recover(&mut self) -> Packet {
+        let ssid = self.missing.drain().next().unwrap();
+        let mut data = std::mem::take(&mut self.data);
+        data.resize(self.length as usize, 0);
+        Packet {
+            id: ssid,
             // Add FEC source symbol ID to the packet.
-            let ssid = self.need_ssid.difference(&self.recv_ssid).next().unwrap();
-            rec.fec = Some(FecMetadata::Source(FecSourceMetadata::Maelstrom(*ssid)));
-            rec.id = *ssid;
-            // Do not forget to say that we do not need the equation anymore!
-            self.recv_ssid.insert(*ssid);
-            Some(rec)
-        } else {
-            None
+            fec: Some(FecMetadata::Source(FecSourceMetadata::new::<MaelstromSSID>(ssid))),
+            recovered: None,
+            data,
+            ..Default::default()
         }
     }
-
-    /// Returns the minimum SSID for this equation.
-    fn get_min_ssid(&self) -> Option<MaelstromSSID> {
-        self.need_ssid.iter().min().copied()
-    }
 }
@@ -1,17 +1,15 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
 use std::fmt::Debug;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::node::decoder::{Decoder, FeedbackReport};
+use crate::node::encoder::Encoder;
+use crate::Error;
 use crate::Packet;
 use crate::Result;
-use crate::Error;
-use networkcoding::RepairSymbol;
-use networkcoding::SourceSymbolMetadata;
-
-use tart::TartEncoder;
-use tart::TartDecoder;
-
-use self::maelstrom::MaelstromDecoder;
-use self::maelstrom::MaelstromEncoder;
-use self::maelstrom::MaelstromRepairInfo;
-use self::maelstrom::MaelstromSSID;
 
 #[derive(Clone, Debug)]
 /// FEC scheme-specific metadata.
@@ -39,20 +37,50 @@ impl FecMetadata {
     }
 }
 
-#[derive(Clone, Debug)]
-/// FEC scheme-specific source metadata.
-pub enum FecSourceMetadata {
-    Tart(SourceSymbolMetadata),
-    Maelstrom(MaelstromSSID),
+/// Metadata of any type that a FEC scheme can attach to its packets.
+pub trait AnyMetadata: Debug {
+    fn as_any(&self) -> &dyn Any;
+
+    fn clone_box(&self) -> Box<dyn AnyMetadata>;
 }
 
-#[derive(Clone, Debug)]
-/// FEC scheme-specific source metadata.
-pub enum FecRepairMetadata {
-    Tart(RepairSymbol),
-    Maelstrom(MaelstromRepairInfo)
+impl<T: Any + Debug + Clone> AnyMetadata for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn AnyMetadata> {
+        Box::new(self.clone())
+    }
+}
+
+#[derive(Debug)]
+/// Type-erased FEC scheme-specific metadata. Each scheme downcasts it to its own type.
+pub struct ErasedMetadata(Box<dyn AnyMetadata>);
+
+impl ErasedMetadata {
+    pub fn new<T: Any + Debug + Clone>(metadata: T) -> Self {
+        Self(Box::new(metadata))
+    }
+
+    /// Returns the metadata if it has the type `T`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.0.as_ref().as_any().downcast_ref()
+    }
+}
+
+impl Clone for ErasedMetadata {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box())
+    }
 }
 
+/// FEC scheme-specific source metadata.
+pub type FecSourceMetadata = ErasedMetadata;
+
+/// FEC scheme-specific repair metadata.
+pub type FecRepairMetadata = ErasedMetadata;
+
 impl Packet {
     pub fn add_fec_metadata(&mut self, metadata: FecMetadata) -> Result<()> {
         if self.fec.is_some() {
@@ -62,91 +90,219 @@ impl Packet {
 
         Ok(())
     }
+
+    /// Returns the source metadata of the packet if it has the type `T`.
+    pub fn source_metadata<T: Any>(&self) -> Option<&T> {
+        self.fec
+            .as_ref()
+            .and_then(FecMetadata::source)
+            .and_then(|m| m.downcast_ref())
+    }
+
+    /// Returns the repair metadata of the packet if it has the type `T`.
+    pub fn repair_metadata<T: Any>(&self) -> Option<&T> {
+        self.fec
+            .as_ref()
+            .and_then(FecMetadata::repair)
+            .and_then(|m| m.downcast_ref())
+    }
 }
 
-/// FEC encoder algorithm.
-pub enum FecEncoder {
-    Tart(TartEncoder),
+/// Scheme-specific statistics, as (name, value) pairs.
+pub type FecStats = Vec<(String, u64)>;
+
+/// FEC encoder algorithm. Implement this trait to add a new FEC scheme.
+pub trait FecEncoderScheme {
+    /// Add FEC metadata for the packet and protect it.
+    fn protect_symbol(&mut self, pkt: &mut Packet) -> Result<()>;
+
+    /// Whether the encoder should generate repair symbols.
+    fn should_generate_rs(&mut self) -> bool;
+
+    /// Generate (potentially several) repair symbols.
+    fn generate_rs(&mut self) -> Result<Vec<Packet>>;
+
+    /// Receive feedback from the decoder. Ignored by default.
+    fn recv_feedback(&mut self, _feedback: &FeedbackReport) {}
 
-    Maelstrom(MaelstromEncoder),
+    /// Generate the repair symbols due at virtual time `now`, e.g., to protect idle traffic. None by default.
+    fn on_timer(&mut self, _now: Duration) -> Result<Vec<Packet>> {
+        Ok(Vec::new())
+    }
+
+    /// Generate the repair symbols protecting the last source symbols at the end of the stream. None by default.
+    fn flush(&mut self) -> Result<Vec<Packet>> {
+        Ok(Vec::new())
+    }
 
-    None,
+    /// Scheme-specific statistics.
+    fn stats(&self) -> FecStats {
+        FecStats::new()
+    }
+
+    /// Name of the scheme with its parameters. Used to name the output files.
+    fn name(&self) -> String;
 }
 
-impl Debug for FecEncoder {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Self::None => write!(f, "none"),
-            Self::Maelstrom(m) => m.fmt(f),
-            Self::Tart(t) => t.fmt(f),
-        }
+/// FEC decoder algorithm. Implement this trait to add a new FEC scheme.
+pub trait FecDecoderScheme {
+    /// Receive a source symbol. Returns recovered packets.
+    fn recv_ss(&mut self, pkt: &Packet) -> Result<Vec<Packet>>;
+
+    /// Receive a repair symbol. Returns recovered packets.
+    fn recv_rs(&mut self, pkt: &Packet) -> Result<Vec<Packet>>;
+
+    /// Scheme-specific statistics.
+    fn stats(&self) -> FecStats {
+        FecStats::new()
     }
+
+    /// Name of the scheme with its parameters.
+    fn name(&self) -> String;
 }
 
-impl FecEncoder {
-    /// Add FEC metadata for the packet and protect it.
-    pub fn protect_symbol(&mut self, pkt: &mut Packet) -> Result<()> {
-        match self {
-            Self::Tart(tart) => tart.protect_symbol(pkt),
-            Self::Maelstrom(mael) => mael.protect_symbol(pkt),
-            Self::None => Ok(()),
-        }
+/// No FEC: packets are forwarded without protection.
+pub struct NoFec;
+
+impl FecEncoderScheme for NoFec {
+    fn protect_symbol(&mut self, _pkt: &mut Packet) -> Result<()> {
+        Ok(())
     }
 
-    /// Whether the encoder should generate repair symbols.
-    pub fn should_generate_rs(&mut self) -> bool {
-        match self {
-            Self::None => false,
-            Self::Tart(tart) => tart.should_send_rs(),
-            Self::Maelstrom(mael) => mael.should_generate_rs(),
-        }
+    fn should_generate_rs(&mut self) -> bool {
+        false
     }
 
-    /// Generate (potentially several) repair symbols.
-    pub fn generate_rs(&mut self) -> Result<Vec<Packet>> {
-        match self {
-            Self::None => Ok(Vec::new()),
-            Self::Tart(tart) => tart.generate_rs(),
-            Self::Maelstrom(mael) => mael.generate_rs(),
-        }
+    fn generate_rs(&mut self) -> Result<Vec<Packet>> {
+        Ok(Vec::new())
     }
 
-    /// Receive feedback from the decoder.
-    pub fn recv_feedback(&mut self, nb_lost: u64, nb_elems: u64) {
-        if let Self::Tart(tart) = self {
-            tart.recv_feedback(nb_lost, nb_elems)
-        }
+    fn name(&self) -> String {
+        "none".to_string()
     }
 }
 
-/// FEC decoder algorithm.
-pub enum FecDecoder {
-    Tart(TartDecoder),
+impl FecDecoderScheme for NoFec {
+    fn recv_ss(&mut self, _pkt: &Packet) -> Result<Vec<Packet>> {
+        Ok(Vec::new())
+    }
 
-    Maelstrom(MaelstromDecoder),
+    fn recv_rs(&mut self, _pkt: &Packet) -> Result<Vec<Packet>> {
+        Ok(Vec::new())
+    }
 
-    None,
+    fn name(&self) -> String {
+        "none".to_string()
+    }
 }
 
-impl FecDecoder {
-    /// Receive a source symbol. Returns recovered packets.
-    pub fn recv_ss(&mut self, pkt: &Packet) -> Result<Vec<Packet>> {
-        match self {
-            Self::Tart(tart) => tart.recv_ss(pkt),
-            Self::Maelstrom(mael) => mael.recv_ss(pkt),
-            Self::None => Ok(Vec::new()),
+#[derive(Clone, Debug, Default)]
+/// Parameters given to the constructors of the [`FecRegistry`], as `key=value` strings.
+pub struct FecParams {
+    params: HashMap<String, String>,
+}
+
+impl FecParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, key: &str, value: impl ToString) {
+        self.params.insert(key.to_string(), value.to_string());
+    }
+
+    /// Returns the parameter parsed as `T`, or `None` if it is missing.
+    /// Fails if it cannot be parsed.
+    pub fn get<T: FromStr>(&self, key: &str) -> Result<Option<T>> {
+        self.params
+            .get(key)
+            .map(|v| {
+                v.parse().map_err(|_| {
+                    Error::FecEncoder(format!("Wrong value of FEC parameter {}: {}", key, v))
+                })
+            })
+            .transpose()
+    }
+
+    pub fn get_or<T: FromStr>(&self, key: &str, default: T) -> Result<T> {
+        Ok(self.get(key)?.unwrap_or(default))
+    }
+
+    /// Adds all parameters of `other`, replacing existing keys.
+    pub fn extend(&mut self, other: &FecParams) {
+        self.params
+            .extend(other.params.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+}
+
+impl From<&str> for FecParams {
+    /// Parses `key=value` pairs separated by commas.
+    fn from(value: &str) -> Self {
+        let mut params = Self::new();
+        value
+            .split(',')
+            .filter_map(|item| item.split_once('='))
+            .for_each(|(key, value)| params.set(key.trim(), value.trim()));
+        params
+    }
+}
+
+/// Builds the encoder and decoder nodes of a FEC scheme from its parameters.
+pub type FecConstructor = Box<dyn Fn(&FecParams) -> Result<(Encoder, Decoder)>>;
+
+/// Registry of FEC schemes, by name. Register a constructor to plug a new scheme in the simulator.
+pub struct FecRegistry {
+    schemes: BTreeMap<String, FecConstructor>,
+}
+
+impl FecRegistry {
+    /// Empty registry. See [`FecRegistry::default`] for a registry with the schemes of this crate.
+    pub fn new() -> Self {
+        Self {
+            schemes: BTreeMap::new(),
         }
     }
 
-    /// Receive a repair symbol. Returns recovered packets.
-    pub fn recv_rs(&mut self, pkt: &Packet) -> Result<Vec<Packet>> {
-        match self {
-            Self::Tart(tart) => tart.recv_rs(pkt),
-            Self::Maelstrom(mael) => mael.recv_rs(pkt),
-            Self::None => Ok(Vec::new()),
+    /// Registers a new scheme. Replaces any scheme with the same name.
+    pub fn register(&mut self, name: &str, constructor: FecConstructor) {
+        self.schemes.insert(name.to_string(), constructor);
+    }
+
+    /// Builds the encoder and decoder of the scheme `name`.
+    pub fn create(&self, name: &str, params: &FecParams) -> Result<(Encoder, Decoder)> {
+        match self.schemes.get(name) {
+            Some(constructor) => constructor(params),
+            None => Err(Error::UnknownFecScheme(name.to_string())),
         }
     }
+
+    /// Names of the registered schemes.
+    pub fn names(&self) -> Vec<&str> {
+        self.schemes.keys().map(|name| name.as_str()).collect()
+    }
+}
+
+impl Default for FecRegistry {
+    fn default() -> Self {
+        let mut registry = Self::new();
+        registry.register(
+            "none",
+            Box::new(|_| Ok((Encoder::new_simple(), Decoder::new_simple()))),
+        );
+        registry.register("tart", Box::new(tart::from_params));
+        registry.register("maelstrom", Box::new(maelstrom::from_params));
+        registry.register("rs", Box::new(rs::from_params));
+        registry.register("xor", Box::new(parity::xor_from_params));
+        registry.register("parity2d", Box::new(parity::parity2d_from_params));
+        registry.register("lt", Box::new(lt::from_params));
+        registry
+    }
 }
 
 pub mod tart;
 pub mod maelstrom;
+pub mod rs;
+pub mod parity;
+pub mod rlc;
+pub mod lt;
+mod gf256;
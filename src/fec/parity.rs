@@ -0,0 +1,206 @@
+use std::fmt::Debug;
+
+use super::maelstrom::{repair_id, xor_repair, MaelstromDecoder, MaelstromSSID};
+use super::FecEncoderScheme;
+use super::FecMetadata;
+use super::FecParams;
+use super::FecSourceMetadata;
+use super::FecStats;
+use crate::node::decoder::Decoder as DecoderNode;
+use crate::node::encoder::Encoder as EncoderNode;
+use crate::Error;
+use crate::Packet;
+use crate::Result;
+
+/// Adds the Maelstrom source metadata to the packet, so that the parity schemes reuse the [`MaelstromDecoder`].
+fn protect(pkt: &mut Packet) -> Result<()> {
+    pkt.add_fec_metadata(FecMetadata::Source(FecSourceMetadata::new::<
+        MaelstromSSID,
+    >(pkt.id)))
+}
+
+/// Single parity per block: a XOR repair symbol every `block_size` source symbols.
+pub struct BlockXorEncoder {
+    /// Number of source symbols protected by a repair symbol.
+    block_size: usize,
+
+    /// Source symbols of the current block.
+    pkts: Vec<Packet>,
+
+    /// Number of repair symbols generated.
+    nb_rs: u64,
+}
+
+impl Debug for BlockXorEncoder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "xor_{}", self.block_size)
+    }
+}
+
+impl BlockXorEncoder {
+    pub fn new(block_size: usize) -> Self {
+        Self {
+            block_size: block_size.max(1),
+            pkts: Vec::with_capacity(block_size),
+            nb_rs: 0,
+        }
+    }
+
+    pub fn get_nb_rs(&self) -> u64 {
+        self.nb_rs
+    }
+}
+
+impl FecEncoderScheme for BlockXorEncoder {
+    fn protect_symbol(&mut self, pkt: &mut Packet) -> Result<()> {
+        protect(pkt)?;
+        self.pkts.push(pkt.clone());
+        Ok(())
+    }
+
+    fn should_generate_rs(&mut self) -> bool {
+        self.pkts.len() >= self.block_size
+    }
+
+    fn generate_rs(&mut self) -> Result<Vec<Packet>> {
+        let repair = xor_repair(self.pkts.iter(), repair_id(self.nb_rs), 0);
+        self.pkts.clear();
+        self.nb_rs += 1;
+        Ok(vec![repair])
+    }
+
+    fn stats(&self) -> FecStats {
+        vec![("repairs".to_string(), self.get_nb_rs())]
+    }
+
+    fn name(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// 2D parity, as in SMPTE 2022-1. Source symbols fill a matrix of `rows` rows and `columns` columns, row by row.
+/// A XOR repair symbol is sent for each row as soon as it is complete, and for each column once the matrix is complete.
+pub struct ParityMatrixEncoder {
+    /// Number of columns (L), i.e., of source symbols in a row.
+    columns: usize,
+
+    /// Number of rows (D), i.e., of source symbols in a column.
+    rows: usize,
+
+    /// Source symbols of the current matrix, row by row.
+    pkts: Vec<Packet>,
+
+    /// Number of rows of the current matrix already protected.
+    nb_rows_protected: usize,
+
+    /// Number of row repair symbols generated.
+    nb_row_rs: u64,
+
+    /// Number of column repair symbols generated.
+    nb_column_rs: u64,
+}
+
+impl Debug for ParityMatrixEncoder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "parity2d_{}_{}", self.columns, self.rows)
+    }
+}
+
+impl ParityMatrixEncoder {
+    pub fn new(columns: usize, rows: usize) -> Self {
+        let (columns, rows) = (columns.max(1), rows.max(1));
+        Self {
+            columns,
+            rows,
+            pkts: Vec::with_capacity(columns * rows),
+            nb_rows_protected: 0,
+            nb_row_rs: 0,
+            nb_column_rs: 0,
+        }
+    }
+
+    pub fn get_nb_rs(&self) -> u64 {
+        self.nb_row_rs + self.nb_column_rs
+    }
+}
+
+impl FecEncoderScheme for ParityMatrixEncoder {
+    fn protect_symbol(&mut self, pkt: &mut Packet) -> Result<()> {
+        protect(pkt)?;
+        self.pkts.push(pkt.clone());
+        Ok(())
+    }
+
+    /// Whether the current row is complete.
+    fn should_generate_rs(&mut self) -> bool {
+        self.pkts.len() >= (self.nb_rows_protected + 1) * self.columns
+    }
+
+    fn generate_rs(&mut self) -> Result<Vec<Packet>> {
+        let start = self.nb_rows_protected * self.columns;
+        let row = self.pkts[start..start + self.columns].iter();
+        let mut out = vec![xor_repair(row, repair_id(self.get_nb_rs()), 0)];
+        self.nb_row_rs += 1;
+        self.nb_rows_protected += 1;
+
+        if self.nb_rows_protected == self.rows {
+            let nb_rs = self.get_nb_rs();
+            out.extend((0..self.columns).map(|column| {
+                let pkts = self.pkts.iter().skip(column).step_by(self.columns);
+                xor_repair(pkts, repair_id(nb_rs + column as u64), 1)
+            }));
+            self.nb_column_rs += self.columns as u64;
+
+            // Start a new matrix.
+            self.pkts.clear();
+            self.nb_rows_protected = 0;
+        }
+
+        Ok(out)
+    }
+
+    fn stats(&self) -> FecStats {
+        vec![
+            ("row-repairs".to_string(), self.nb_row_rs),
+            ("column-repairs".to_string(), self.nb_column_rs),
+        ]
+    }
+
+    fn name(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// Builds a block XOR encoder and a Maelstrom decoder from the parameters:
+/// - `block`: number of source symbols protected by a repair symbol (default: 10);
+/// - `capacity`: maximum number of source symbols stored by the decoder (default: 20 times the block).
+pub fn xor_from_params(params: &FecParams) -> Result<(EncoderNode, DecoderNode)> {
+    let block: usize = params.get_or("block", 10)?;
+    if block == 0 {
+        return Err(Error::FecEncoder("Empty XOR block".to_string()));
+    }
+
+    let encoder = EncoderNode::new(Box::new(BlockXorEncoder::new(block)));
+    let decoder = MaelstromDecoder::new(params.get_or("capacity", block * 20)?);
+    let decoder = DecoderNode::new(Box::new(decoder), None);
+
+    Ok((encoder, decoder))
+}
+
+/// Builds a 2D parity encoder and a Maelstrom decoder from the parameters:
+/// - `columns`: number of columns L of the matrix (default: 10);
+/// - `rows`: number of rows D of the matrix (default: 10);
+/// - `capacity`: maximum number of source symbols stored by the decoder (default: twice the matrix).
+pub fn parity2d_from_params(params: &FecParams) -> Result<(EncoderNode, DecoderNode)> {
+    let columns: usize = params.get_or("columns", 10)?;
+    let rows: usize = params.get_or("rows", 10)?;
+    if columns == 0 || rows == 0 {
+        return Err(Error::FecEncoder("Empty parity matrix".to_string()));
+    }
+
+    let encoder = EncoderNode::new(Box::new(ParityMatrixEncoder::new(columns, rows)));
+    let decoder = MaelstromDecoder::new(params.get_or("capacity", columns * rows * 2)?);
+    let decoder = DecoderNode::new(Box::new(decoder), None);
+
+    Ok((encoder, decoder))
+}
@@ -0,0 +1,402 @@
+//! In-crate sliding-window Random Linear Code (RLC) over GF(2^8).
+//! Mirrors the API of the private `networkcoding` library used by the original TART implementation.
+
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use super::gf256;
+
+pub type SourceSymbolMetadata = [u8; 8];
+
+pub fn source_symbol_metadata_from_u64(id: u64) -> SourceSymbolMetadata {
+    id.to_be_bytes()
+}
+
+pub fn source_symbol_metadata_to_u64(metadata: SourceSymbolMetadata) -> u64 {
+    u64::from_be_bytes(metadata)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A source symbol and its ID.
+pub struct SourceSymbol {
+    metadata: SourceSymbolMetadata,
+    data: Vec<u8>,
+}
+
+impl SourceSymbol {
+    pub fn new(metadata: SourceSymbolMetadata, data: Vec<u8>) -> Self {
+        Self { metadata, data }
+    }
+
+    pub fn get(&self) -> &Vec<u8> {
+        &self.data
+    }
+
+    pub fn metadata(&self) -> SourceSymbolMetadata {
+        self.metadata
+    }
+}
+
+#[derive(Clone, Debug)]
+/// A repair symbol: a random linear combination of the source symbols of the window.
+pub struct RepairSymbol {
+    /// ID of the first source symbol of the window.
+    first_id: u64,
+
+    /// Number of source symbols in the window.
+    nb_symbols: u64,
+
+    /// Seed of the coefficients of the combination.
+    seed: u64,
+
+    /// Combination of the coded source symbols.
+    payload: Vec<u8>,
+}
+
+impl RepairSymbol {
+    /// Moves the combination out of the repair symbol, e.g., to send it as the packet payload.
+    pub fn take_payload(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.payload)
+    }
+
+    pub fn set_payload(&mut self, payload: Vec<u8>) {
+        self.payload = payload;
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum EncoderError {
+    NoSymbolToGenerate,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecoderError {
+    /// The repair symbol does not bring new information.
+    UnusedRepairSymbol,
+
+    /// The repair symbol protects no source symbol.
+    EmptyRepairSymbol,
+}
+
+/// Non-zero coefficients of a repair symbol, one for each source symbol of its window.
+fn coefficients(seed: u64, nb_symbols: u64) -> impl Iterator<Item = u8> {
+    let mut rng = SmallRng::seed_from_u64(seed);
+    (0..nb_symbols).map(move |_| rng.gen_range(1..=255))
+}
+
+/// Sliding-window RLC encoder.
+pub struct RlcEncoder {
+    /// Coded source symbols of the window, by increasing ID.
+    window: VecDeque<(u64, Vec<u8>)>,
+
+    /// ID of the next protected source symbol.
+    next_id: u64,
+
+    /// Maximum number of source symbols in the window. The oldest symbols are removed beyond this size.
+    max_window: usize,
+
+    /// Number of generated repair symbols, used to derive the seed of the coefficients.
+    nb_repairs: u64,
+}
+
+impl RlcEncoder {
+    pub fn new(max_window: usize) -> Self {
+        Self {
+            window: VecDeque::with_capacity(max_window),
+            next_id: 0,
+            max_window: max_window.max(1),
+            nb_repairs: 0,
+        }
+    }
+
+    pub fn next_metadata(&self) -> Result<SourceSymbolMetadata, EncoderError> {
+        Ok(source_symbol_metadata_from_u64(self.next_id))
+    }
+
+    /// Adds the data to the window and sets its metadata.
+    pub fn protect_data(
+        &mut self,
+        data: Vec<u8>,
+        metadata: &mut SourceSymbolMetadata,
+    ) -> Result<(), EncoderError> {
+        *metadata = source_symbol_metadata_from_u64(self.next_id);
+        self.window.push_back((self.next_id, gf256::to_symbol(&data)));
+        self.next_id += 1;
+        while self.window.len() > self.max_window {
+            self.window.pop_front();
+        }
+        Ok(())
+    }
+
+    pub fn n_protected_symbols(&self) -> usize {
+        self.window.len()
+    }
+
+    /// Removes the source symbols with an ID strictly lower than `metadata` from the window.
+    pub fn remove_up_to(&mut self, metadata: SourceSymbolMetadata) {
+        let id = source_symbol_metadata_to_u64(metadata);
+        while self.window.front().is_some_and(|(first, _)| *first < id) {
+            self.window.pop_front();
+        }
+    }
+
+    pub fn generate_and_serialize_repair_symbol(&mut self) -> Result<RepairSymbol, EncoderError> {
+        let first_id = match self.window.front() {
+            Some((id, _)) => *id,
+            None => return Err(EncoderError::NoSymbolToGenerate),
+        };
+
+        let seed = self.nb_repairs;
+        self.nb_repairs += 1;
+        let nb_symbols = self.window.len() as u64;
+        let mut payload = Vec::new();
+        for ((_, symbol), coef) in self.window.iter().zip(coefficients(seed, nb_symbols)) {
+            gf256::add_mul(&mut payload, symbol, coef);
+        }
+
+        Ok(RepairSymbol {
+            first_id,
+            nb_symbols,
+            seed,
+            payload,
+        })
+    }
+}
+
+/// Linear equation over unknown source symbols, with a payload equal to the combination of the coded symbols.
+struct Equation {
+    /// Non-zero coefficients, by source symbol ID. The pivot is the first one.
+    coefs: BTreeMap<u64, u8>,
+
+    payload: Vec<u8>,
+}
+
+impl Equation {
+    fn pivot(&self) -> Option<u64> {
+        self.coefs.keys().next().copied()
+    }
+
+    /// `self += coef * other`.
+    fn add_mul(&mut self, other: &Equation, coef: u8) {
+        for (&id, &c) in other.coefs.iter() {
+            let value = self.coefs.entry(id).or_insert(0);
+            *value ^= gf256::mul(c, coef);
+            if *value == 0 {
+                self.coefs.remove(&id);
+            }
+        }
+        gf256::add_mul(&mut self.payload, &other.payload, coef);
+    }
+}
+
+/// Sliding-window RLC decoder, performing the Gaussian elimination on the fly.
+/// The equations are kept in reduced row echelon form: a pivot appears in no other equation.
+pub struct RlcDecoder {
+    /// Coded source symbols received or recovered, by ID.
+    known: BTreeMap<u64, Vec<u8>>,
+
+    /// Equations with unknown source symbols, by pivot.
+    equations: BTreeMap<u64, Equation>,
+
+    /// Maximum distance between the oldest and the newest source symbols stored.
+    max_window: u64,
+
+    /// Highest source symbol ID seen.
+    max_id: u64,
+}
+
+impl RlcDecoder {
+    pub fn new(max_window: usize) -> Self {
+        Self {
+            known: BTreeMap::new(),
+            equations: BTreeMap::new(),
+            max_window: max_window.max(1) as u64,
+            max_id: 0,
+        }
+    }
+
+    /// Number of equations waiting for missing source symbols.
+    pub fn n_equations(&self) -> usize {
+        self.equations.len()
+    }
+
+    pub fn receive_source_symbol(
+        &mut self,
+        symbol: SourceSymbol,
+        _now: Instant,
+    ) -> Result<Vec<SourceSymbol>, DecoderError> {
+        let id = source_symbol_metadata_to_u64(symbol.metadata);
+        self.slide(id);
+        if self.known.contains_key(&id) {
+            return Ok(Vec::new());
+        }
+
+        let mut recovered = self.add_known(id, gf256::to_symbol(&symbol.data));
+        // The received symbol itself is not a recovered one.
+        recovered.retain(|s| s.metadata != symbol.metadata);
+        Ok(recovered)
+    }
+
+    pub fn receive_and_deserialize_repair_symbol(
+        &mut self,
+        repair: RepairSymbol,
+    ) -> Result<((), Vec<SourceSymbol>), DecoderError> {
+        if repair.nb_symbols == 0 {
+            return Err(DecoderError::EmptyRepairSymbol);
+        }
+        self.slide(repair.first_id + repair.nb_symbols - 1);
+
+        // Subtract the known source symbols, and ignore the ones out of the window.
+        let oldest = self.oldest_id();
+        let mut equation = Equation {
+            coefs: BTreeMap::new(),
+            payload: repair.payload,
+        };
+        let ids = repair.first_id..repair.first_id + repair.nb_symbols;
+        for (id, coef) in ids.zip(coefficients(repair.seed, repair.nb_symbols)) {
+            match self.known.get(&id) {
+                Some(symbol) => gf256::add_mul(&mut equation.payload, symbol, coef),
+                None if id < oldest => return Err(DecoderError::UnusedRepairSymbol),
+                None => {
+                    equation.coefs.insert(id, coef);
+                }
+            }
+        }
+
+        let recovered = self.add_equation(equation)?;
+        Ok(((), recovered))
+    }
+
+    /// Removes the source symbols and equations with an ID strictly lower than `metadata`.
+    pub fn remove_up_to(&mut self, metadata: SourceSymbolMetadata, _now: Option<Instant>) {
+        let id = source_symbol_metadata_to_u64(metadata);
+        self.known = self.known.split_off(&id);
+        self.equations.retain(|_, eq| eq.pivot().is_some_and(|p| p >= id));
+    }
+
+    fn oldest_id(&self) -> u64 {
+        self.max_id.saturating_sub(self.max_window)
+    }
+
+    /// Updates the highest ID seen and removes what is out of the window.
+    fn slide(&mut self, id: u64) {
+        if id > self.max_id {
+            self.max_id = id;
+            let oldest = self.oldest_id();
+            if self.known.first_key_value().is_some_and(|(first, _)| *first < oldest)
+                || self.equations.first_key_value().is_some_and(|(first, _)| *first < oldest)
+            {
+                self.remove_up_to(source_symbol_metadata_from_u64(oldest), None);
+            }
+        }
+    }
+
+    /// Adds a new equation to the system. Returns the recovered source symbols.
+    fn add_equation(&mut self, mut equation: Equation) -> Result<Vec<SourceSymbol>, DecoderError> {
+        // Eliminate the pivots of the existing equations.
+        let pivots: Vec<u64> = equation
+            .coefs
+            .keys()
+            .filter(|id| self.equations.contains_key(id))
+            .copied()
+            .collect();
+        for pivot in pivots {
+            if let Some(&coef) = equation.coefs.get(&pivot) {
+                equation.add_mul(&self.equations[&pivot], coef);
+            }
+        }
+
+        // Normalise the new pivot.
+        let pivot = equation.pivot().ok_or(DecoderError::UnusedRepairSymbol)?;
+        let inv = gf256::inv(equation.coefs[&pivot]);
+        equation.coefs.values_mut().for_each(|c| *c = gf256::mul(*c, inv));
+        gf256::scale(&mut equation.payload, inv);
+
+        // Eliminate the new pivot from the existing equations.
+        for other in self.equations.values_mut() {
+            if let Some(&coef) = other.coefs.get(&pivot) {
+                other.add_mul(&equation, coef);
+            }
+        }
+        self.equations.insert(pivot, equation);
+
+        Ok(self.collect_solved())
+    }
+
+    /// Adds a source symbol whose value is known, and propagates it through the equations.
+    fn add_known(&mut self, id: u64, symbol: Vec<u8>) -> Vec<SourceSymbol> {
+        let mut recovered = Vec::new();
+        let mut queue = vec![(id, symbol)];
+
+        while let Some((id, symbol)) = queue.pop() {
+            if self.known.contains_key(&id) {
+                continue;
+            }
+
+            // Substitute the symbol in the equations where it is not the pivot.
+            for (_, equation) in self.equations.iter_mut().filter(|(pivot, _)| **pivot != id) {
+                if let Some(coef) = equation.coefs.remove(&id) {
+                    gf256::add_mul(&mut equation.payload, &symbol, coef);
+                }
+            }
+            self.known.insert(id, symbol.clone());
+            recovered.push(id);
+
+            // The equation with this pivot becomes a new equation on the remaining symbols.
+            if let Some(mut equation) = self.equations.remove(&id) {
+                equation.coefs.remove(&id);
+                gf256::add_mul(&mut equation.payload, &symbol, 1);
+                if !equation.coefs.is_empty() {
+                    recovered.extend(
+                        self.add_equation(equation)
+                            .unwrap_or_default()
+                            .iter()
+                            .map(|s| source_symbol_metadata_to_u64(s.metadata)),
+                    );
+                }
+            }
+
+            queue.extend(self.take_solved());
+        }
+
+        self.to_source_symbols(recovered)
+    }
+
+    /// Removes the equations with a single unknown, i.e., their pivot, and returns the solved symbols.
+    fn take_solved(&mut self) -> Vec<(u64, Vec<u8>)> {
+        let solved: Vec<u64> = self
+            .equations
+            .iter()
+            .filter(|(_, eq)| eq.coefs.len() == 1)
+            .map(|(&pivot, _)| pivot)
+            .collect();
+        solved
+            .into_iter()
+            .filter_map(|pivot| self.equations.remove(&pivot).map(|eq| (pivot, eq.payload)))
+            .collect()
+    }
+
+    fn collect_solved(&mut self) -> Vec<SourceSymbol> {
+        let mut recovered = Vec::new();
+        for (id, symbol) in self.take_solved() {
+            recovered.extend(self.add_known(id, symbol));
+        }
+        recovered
+    }
+
+    fn to_source_symbols(&self, ids: Vec<u64>) -> Vec<SourceSymbol> {
+        let mut seen = HashSet::new();
+        ids.into_iter()
+            .filter(|id| seen.insert(*id))
+            .filter_map(|id| {
+                let data = gf256::from_symbol(self.known.get(&id)?)?;
+                Some(SourceSymbol::new(source_symbol_metadata_from_u64(id), data))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,382 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use super::gf256::{self, from_symbol, to_symbol};
+use super::maelstrom::repair_id;
+use super::FecDecoderScheme;
+use super::FecEncoderScheme;
+use super::FecMetadata;
+use super::FecParams;
+use super::FecRepairMetadata;
+use super::FecSourceMetadata;
+use super::FecStats;
+use crate::node::decoder::Decoder as DecoderNode;
+use crate::node::encoder::Encoder as EncoderNode;
+use crate::Error;
+use crate::Packet;
+use crate::Result;
+
+/// Row of the generator matrix for the symbol at `index` in a block of `k` source symbols.
+/// Source symbols use the identity (systematic code); repair symbols use a Cauchy matrix, so that any `k` rows are independent.
+fn generator_row(index: usize, k: usize) -> Vec<u8> {
+    (0..k)
+        .map(|i| {
+            if index < k {
+                (index == i) as u8
+            } else {
+                gf256::inv(index as u8 ^ i as u8)
+            }
+        })
+        .collect()
+}
+
+/// Inverts a square matrix with Gauss-Jordan elimination. Returns `None` if the matrix is singular.
+fn invert(mut matrix: Vec<Vec<u8>>) -> Option<Vec<Vec<u8>>> {
+    let k = matrix.len();
+    let mut inverse: Vec<Vec<u8>> = (0..k).map(|i| generator_row(i, k)).collect();
+
+    for col in 0..k {
+        let pivot = (col..k).find(|&row| matrix[row][col] != 0)?;
+        matrix.swap(col, pivot);
+        inverse.swap(col, pivot);
+
+        let inv = gf256::inv(matrix[col][col]);
+        matrix[col].iter_mut().for_each(|v| *v = gf256::mul(*v, inv));
+        inverse[col].iter_mut().for_each(|v| *v = gf256::mul(*v, inv));
+
+        for row in 0..k {
+            let coef = matrix[row][col];
+            if row != col && coef != 0 {
+                let (pivot_row, pivot_inv) = (matrix[col].clone(), inverse[col].clone());
+                gf256::add_mul(&mut matrix[row], &pivot_row, coef);
+                gf256::add_mul(&mut inverse[row], &pivot_inv, coef);
+            }
+        }
+    }
+
+    Some(inverse)
+}
+
+#[derive(Clone, Debug)]
+/// Reed-Solomon source FEC information.
+pub struct RsSourceInfo {
+    /// Block of the source symbol.
+    block: u64,
+
+    /// Index of the source symbol in its block.
+    index: usize,
+}
+
+#[derive(Clone, Debug)]
+/// Reed-Solomon repair FEC information.
+pub struct RsRepairInfo {
+    /// Block protected by the repair symbol.
+    block: u64,
+
+    /// Index of the repair symbol in its block, between `k` and `n`.
+    index: usize,
+
+    /// IDs of the source symbols of the block.
+    ids: Vec<u64>,
+}
+
+/// Systematic Reed-Solomon encoder over GF(2^8). Generates `n - k` repair symbols for each block of `k` source symbols.
+pub struct ReedSolomonEncoder {
+    /// Number of source symbols in a block.
+    k: usize,
+
+    /// Number of source and repair symbols in a block.
+    n: usize,
+
+    /// Current block.
+    block: u64,
+
+    /// IDs and coded payloads of the source symbols of the current block.
+    symbols: Vec<(u64, Vec<u8>)>,
+}
+
+impl Debug for ReedSolomonEncoder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "rs_{}_{}", self.k, self.n)
+    }
+}
+
+impl ReedSolomonEncoder {
+    /// Requires `0 < k < n <= 256`.
+    pub fn new(k: usize, n: usize) -> Result<Self> {
+        check_parameters(k, n)?;
+        Ok(Self {
+            k,
+            n,
+            block: 0,
+            symbols: Vec::with_capacity(k),
+        })
+    }
+
+    /// Number of complete blocks, i.e., for which repair symbols were generated.
+    pub fn get_nb_blocks(&self) -> u64 {
+        self.block
+    }
+}
+
+impl FecEncoderScheme for ReedSolomonEncoder {
+    fn protect_symbol(&mut self, pkt: &mut Packet) -> Result<()> {
+        pkt.add_fec_metadata(FecMetadata::Source(FecSourceMetadata::new(RsSourceInfo {
+            block: self.block,
+            index: self.symbols.len(),
+        })))?;
+        self.symbols.push((pkt.id, to_symbol(&pkt.data)));
+
+        Ok(())
+    }
+
+    fn should_generate_rs(&mut self) -> bool {
+        self.symbols.len() >= self.k
+    }
+
+    fn generate_rs(&mut self) -> Result<Vec<Packet>> {
+        let len = self.symbols.iter().map(|(_, s)| s.len()).max().unwrap_or(0);
+        let ids: Vec<u64> = self.symbols.iter().map(|(id, _)| *id).collect();
+        let first_repair = self.block * (self.n - self.k) as u64;
+
+        let out = (self.k..self.n)
+            .map(|index| {
+                let mut data = vec![0; len];
+                for ((_, symbol), coef) in self.symbols.iter().zip(generator_row(index, self.k)) {
+                    gf256::add_mul(&mut data, symbol, coef);
+                }
+                let repair_info = RsRepairInfo {
+                    block: self.block,
+                    index,
+                    ids: ids.clone(),
+                };
+                Packet {
+                    id: repair_id(first_repair + (index - self.k) as u64),
+                    fec: Some(FecMetadata::Repair(FecRepairMetadata::new(repair_info))),
+                    recovered: None,
+                    data,
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        // Start a new block.
+        self.symbols.clear();
+        self.block += 1;
+
+        Ok(out)
+    }
+
+    fn stats(&self) -> FecStats {
+        vec![("blocks".to_string(), self.get_nb_blocks())]
+    }
+
+    fn name(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+/// Received symbols of a block.
+#[derive(Default)]
+struct Block {
+    /// IDs of the source symbols, known once a repair symbol is received.
+    ids: Option<Vec<u64>>,
+
+    /// Coded symbols, by index in the block. Source symbols come first.
+    symbols: BTreeMap<usize, Vec<u8>>,
+
+    /// Whether all source symbols of the block are received or recovered.
+    decoded: bool,
+}
+
+/// Systematic Reed-Solomon decoder over GF(2^8). Recovers a block as soon as any `k` of its `n` symbols are received.
+pub struct ReedSolomonDecoder {
+    /// Number of source symbols in a block.
+    k: usize,
+
+    /// Number of source and repair symbols in a block.
+    n: usize,
+
+    /// Blocks that are still in scope.
+    blocks: BTreeMap<u64, Block>,
+
+    /// Maximum number of blocks stored.
+    capacity: usize,
+
+    /// Number of blocks with lost source symbols that were recovered.
+    nb_decoded: u64,
+
+    /// Number of blocks with lost source symbols removed from the decoder without being recovered.
+    nb_unrecoverable: u64,
+
+    /// Highest ID of the received source symbols. Repair symbols recover from the next one.
+    max_ssid: u64,
+}
+
+impl ReedSolomonDecoder {
+    /// Requires `0 < k < n <= 256`.
+    pub fn new(k: usize, n: usize, capacity: usize) -> Result<Self> {
+        check_parameters(k, n)?;
+        Ok(Self {
+            k,
+            n,
+            blocks: BTreeMap::new(),
+            capacity: capacity.max(1),
+            nb_decoded: 0,
+            nb_unrecoverable: 0,
+            max_ssid: 0,
+        })
+    }
+
+    pub fn get_nb_decoded(&self) -> u64 {
+        self.nb_decoded
+    }
+
+    pub fn get_nb_unrecoverable(&self) -> u64 {
+        self.nb_unrecoverable
+    }
+
+    /// Returns the block, creating it if needed, and removes the blocks out of the capacity.
+    fn get_block(&mut self, block: u64) -> Result<&mut Block> {
+        if let Some((&last, _)) = self.blocks.last_key_value() {
+            if block + self.capacity as u64 <= last && !self.blocks.contains_key(&block) {
+                return Err(Error::TooOldEquation);
+            }
+        }
+
+        let k = self.k;
+        while self.blocks.len() >= self.capacity && !self.blocks.contains_key(&block) {
+            if let Some((_, old)) = self.blocks.pop_first() {
+                if !old.decoded && old.symbols.range(..k).count() < k {
+                    self.nb_unrecoverable += 1;
+                }
+            }
+        }
+
+        Ok(self.blocks.entry(block).or_default())
+    }
+
+    /// Recovers the lost source symbols of the block if enough symbols are received.
+    /// `from` is the ID of the received packet that triggered the recovery.
+    fn try_decode(&mut self, block_id: u64, from: u64) -> Result<Vec<Packet>> {
+        let k = self.k;
+        let block = match self.blocks.get_mut(&block_id) {
+            Some(block) => block,
+            None => return Ok(Vec::new()),
+        };
+        if block.decoded {
+            return Ok(Vec::new());
+        }
+
+        let missing: Vec<usize> = (0..k).filter(|i| !block.symbols.contains_key(i)).collect();
+        if missing.is_empty() {
+            block.decoded = true;
+            block.symbols.clear();
+            return Ok(Vec::new());
+        }
+        let ids = match block.ids.as_ref() {
+            Some(ids) if block.symbols.len() >= k => ids,
+            _ => return Ok(Vec::new()),
+        };
+
+        // Solve the system using the first k received symbols.
+        let received: Vec<(&usize, &Vec<u8>)> = block.symbols.iter().take(k).collect();
+        let matrix = received.iter().map(|(&index, _)| generator_row(index, k)).collect();
+        let inverse = invert(matrix).ok_or(Error::FecDecoder("Singular matrix".to_string()))?;
+        let len = received.iter().map(|(_, s)| s.len()).max().unwrap_or(0);
+
+        let mut recovered = Vec::with_capacity(missing.len());
+        for index in missing {
+            let mut symbol = vec![0; len];
+            for ((_, data), &coef) in received.iter().zip(inverse[index].iter()) {
+                gf256::add_mul(&mut symbol, data, coef);
+            }
+            let data = from_symbol(&symbol).ok_or(Error::FecWrongMetadata)?;
+            let id = *ids.get(index).ok_or(Error::FecWrongMetadata)?;
+            recovered.push(Packet {
+                id,
+                fec: Some(FecMetadata::Source(FecSourceMetadata::new(RsSourceInfo {
+                    block: block_id,
+                    index,
+                }))),
+                recovered: Some(from.saturating_sub(id)),
+                data,
+                ..Default::default()
+            });
+        }
+
+        block.decoded = true;
+        block.symbols.clear();
+        self.nb_decoded += 1;
+
+        Ok(recovered)
+    }
+}
+
+impl FecDecoderScheme for ReedSolomonDecoder {
+    fn recv_ss(&mut self, pkt: &Packet) -> Result<Vec<Packet>> {
+        let info = pkt
+            .source_metadata::<RsSourceInfo>()
+            .ok_or(Error::FecWrongMetadata)?;
+        let block = self.get_block(info.block)?;
+        if !block.decoded {
+            block.symbols.insert(info.index, to_symbol(&pkt.data));
+        }
+        self.max_ssid = self.max_ssid.max(pkt.id);
+        self.try_decode(info.block, pkt.id)
+    }
+
+    fn recv_rs(&mut self, pkt: &Packet) -> Result<Vec<Packet>> {
+        let info = pkt
+            .repair_metadata::<RsRepairInfo>()
+            .ok_or(Error::FecWrongMetadata)?;
+        if info.index < self.k || info.index >= self.n || info.ids.len() != self.k {
+            return Err(Error::FecWrongMetadata);
+        }
+        let block = self.get_block(info.block)?;
+        if block.decoded {
+            return Err(Error::UnusedRepair);
+        }
+        block.symbols.insert(info.index, pkt.data.clone());
+        block.ids.get_or_insert_with(|| info.ids.clone());
+        self.try_decode(info.block, self.max_ssid + 1)
+    }
+
+    fn stats(&self) -> FecStats {
+        vec![
+            ("decoded-blocks".to_string(), self.get_nb_decoded()),
+            ("unrecoverable-blocks".to_string(), self.get_nb_unrecoverable()),
+        ]
+    }
+
+    fn name(&self) -> String {
+        format!("rs_{}_{}", self.k, self.n)
+    }
+}
+
+fn check_parameters(k: usize, n: usize) -> Result<()> {
+    if k == 0 || n <= k || n > 256 {
+        return Err(Error::FecEncoder(format!(
+            "Reed-Solomon requires 0 < k < n <= 256, got k={} and n={}",
+            k, n
+        )));
+    }
+    Ok(())
+}
+
+/// Builds a Reed-Solomon encoder and decoder from the parameters:
+/// - `k`: number of source symbols in a block (default: 10);
+/// - `n`: number of source and repair symbols in a block (default: 12);
+/// - `capacity`: maximum number of blocks stored by the decoder (default: 20).
+pub fn from_params(params: &FecParams) -> Result<(EncoderNode, DecoderNode)> {
+    let k = params.get_or("k", 10)?;
+    let n = params.get_or("n", 12)?;
+
+    let encoder = ReedSolomonEncoder::new(k, n)?;
+    let encoder = EncoderNode::new(Box::new(encoder));
+
+    let decoder = ReedSolomonDecoder::new(k, n, params.get_or("capacity", 20)?)?;
+    let decoder = DecoderNode::new(Box::new(decoder), None);
+
+    Ok((encoder, decoder))
+}
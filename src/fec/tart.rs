@@ -1,50 +1,486 @@
-use super::FecRepairMetadata;
+use super::rlc::{
+    source_symbol_metadata_from_u64, source_symbol_metadata_to_u64, DecoderError, RepairSymbol,
+    RlcDecoder, RlcEncoder, SourceSymbol, SourceSymbolMetadata,
+};
+use super::ErasedMetadata;
+use super::FecDecoderScheme;
+use super::FecEncoderScheme;
+use super::FecParams;
 use super::FecSourceMetadata;
+use super::FecStats;
+use crate::node::decoder::{Decoder as DecoderNode, DecoderFeedback, FeedbackReport, LossEncoding};
+use crate::node::encoder::Encoder as EncoderNode;
 use crate::Error;
 use crate::FecMetadata;
 use crate::Packet;
 use crate::Result;
-#[cfg(feature = "rlc")]
-use networkcoding::rlc::decoder::RLCDecoder;
-#[cfg(feature = "rlc")]
-use networkcoding::rlc::encoder::RLCEncoder;
-use networkcoding::source_symbol_metadata_from_u64;
-use networkcoding::source_symbol_metadata_to_u64;
-use networkcoding::vandermonde_lc::decoder::VLCDecoder;
-use networkcoding::vandermonde_lc::encoder::VLCEncoder;
-use networkcoding::Decoder;
-use networkcoding::Encoder;
-use networkcoding::SourceSymbol;
-use networkcoding::SourceSymbolMetadata;
+use std::collections::BTreeSet;
 use std::fmt::Debug;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 const MAX_WINDOW_FACTOR: usize = 500;
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+/// Implementation of the sliding-window code used by TART.
+pub enum TartBackend {
+    /// RLC of the `networkcoding` crate.
+    Rlc,
+
+    /// Vandermonde linear code of the `networkcoding` crate.
+    Vlc,
+
+    /// In-crate RLC, see [`super::rlc`].
+    Native,
+}
+
+impl Default for TartBackend {
+    /// The `networkcoding` VLC if available (RLC with the `rlc` feature), the in-crate RLC otherwise.
+    fn default() -> Self {
+        if cfg!(not(feature = "networkcoding")) {
+            Self::Native
+        } else if cfg!(feature = "rlc") {
+            Self::Rlc
+        } else {
+            Self::Vlc
+        }
+    }
+}
+
+impl TryFrom<&str> for TartBackend {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self> {
+        match value {
+            "rlc" => Ok(Self::Rlc),
+            "vlc" => Ok(Self::Vlc),
+            "native" => Ok(Self::Native),
+            _ => Err(Error::FecEncoder(format!("Unknown TART backend: {}", value))),
+        }
+    }
+}
+
+impl TartBackend {
+    /// Default ratio between the capacity of the coding window and the maximum FEC window.
+    pub fn default_window_factor(&self) -> usize {
+        match self {
+            Self::Rlc => 10,
+            Self::Vlc => MAX_WINDOW_FACTOR,
+            Self::Native => 2,
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Self::Rlc => "rlc",
+            Self::Vlc => "vlc",
+            Self::Native => "native",
+        }
+    }
+}
+
+#[derive(Clone)]
+/// Configuration of the sliding-window code used by TART.
+pub struct TartCodec {
+    backend: TartBackend,
+
+    /// Symbol size given to the `networkcoding` backends.
+    symbol_size: usize,
+
+    /// Ratio between the capacity of the coding window and the maximum FEC window.
+    window_factor: usize,
+}
+
+impl TartCodec {
+    pub fn new(backend: TartBackend) -> Self {
+        Self {
+            backend,
+            symbol_size: 8,
+            window_factor: backend.default_window_factor(),
+        }
+    }
+
+    pub fn set_symbol_size(&mut self, symbol_size: usize) {
+        self.symbol_size = symbol_size;
+    }
+
+    pub fn set_window_factor(&mut self, window_factor: usize) {
+        self.window_factor = window_factor.max(1);
+    }
+
+    pub fn get_backend(&self) -> TartBackend {
+        self.backend
+    }
+
+    pub fn get_symbol_size(&self) -> usize {
+        self.symbol_size
+    }
+
+    pub fn get_window_factor(&self) -> usize {
+        self.window_factor
+    }
+
+    #[cfg(not(feature = "networkcoding"))]
+    fn unavailable(&self) -> Error {
+        Error::FecEncoder(format!(
+            "The {} TART backend requires the networkcoding feature",
+            self.backend.name()
+        ))
+    }
+}
+
+impl Default for TartCodec {
+    fn default() -> Self {
+        Self::new(TartBackend::default())
+    }
+}
+
+impl Debug for TartCodec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}_s{}_w{}",
+            self.backend.name(),
+            self.symbol_size,
+            self.window_factor
+        )
+    }
+}
+
+fn encoder_error(e: impl Debug) -> Error {
+    Error::FecEncoder(format!("{:?}", e))
+}
+
+/// Sliding-window encoder of the chosen backend, working on the IDs of the simulator.
+enum CodecEncoder {
+    #[cfg(feature = "networkcoding")]
+    NetworkCoding(networkcoding::Encoder),
+
+    Native(RlcEncoder),
+}
+
+impl CodecEncoder {
+    fn new(codec: &TartCodec, max_wnd: usize) -> Result<Self> {
+        let capacity = max_wnd * codec.window_factor;
+        Ok(match codec.backend {
+            #[cfg(feature = "networkcoding")]
+            TartBackend::Rlc => Self::NetworkCoding(networkcoding::Encoder::RLC(
+                networkcoding::rlc::encoder::RLCEncoder::new(codec.symbol_size, capacity, 1),
+            )),
+            #[cfg(feature = "networkcoding")]
+            TartBackend::Vlc => Self::NetworkCoding(networkcoding::Encoder::VLC(
+                networkcoding::vandermonde_lc::encoder::VLCEncoder::new(
+                    codec.symbol_size,
+                    capacity,
+                ),
+            )),
+            #[cfg(not(feature = "networkcoding"))]
+            TartBackend::Rlc | TartBackend::Vlc => return Err(codec.unavailable()),
+            TartBackend::Native => Self::Native(RlcEncoder::new(capacity)),
+        })
+    }
+
+    fn next_id(&self) -> u64 {
+        match self {
+            #[cfg(feature = "networkcoding")]
+            Self::NetworkCoding(tart) => {
+                networkcoding::source_symbol_metadata_to_u64(tart.next_metadata().unwrap())
+            }
+            Self::Native(tart) => source_symbol_metadata_to_u64(tart.next_metadata().unwrap()),
+        }
+    }
+
+    /// Protects the data and returns its metadata.
+    fn protect(&mut self, data: Vec<u8>) -> Result<SourceSymbolMetadata> {
+        match self {
+            #[cfg(feature = "networkcoding")]
+            Self::NetworkCoding(tart) => {
+                let mut metadata = tart.next_metadata().map_err(encoder_error)?;
+                tart.protect_data(data, &mut metadata)
+                    .map_err(encoder_error)?;
+                Ok(metadata)
+            }
+            Self::Native(tart) => {
+                let mut metadata = tart.next_metadata().map_err(encoder_error)?;
+                tart.protect_data(data, &mut metadata)
+                    .map_err(encoder_error)?;
+                Ok(metadata)
+            }
+        }
+    }
+
+    fn n_protected_symbols(&self) -> usize {
+        match self {
+            #[cfg(feature = "networkcoding")]
+            Self::NetworkCoding(tart) => tart.n_protected_symbols(),
+            Self::Native(tart) => tart.n_protected_symbols(),
+        }
+    }
+
+    fn remove_up_to(&mut self, id: u64) {
+        match self {
+            #[cfg(feature = "networkcoding")]
+            Self::NetworkCoding(tart) => {
+                tart.remove_up_to(networkcoding::source_symbol_metadata_from_u64(id))
+            }
+            Self::Native(tart) => tart.remove_up_to(source_symbol_metadata_from_u64(id)),
+        }
+    }
+
+    /// Generates a repair symbol. Returns its metadata and its payload, sent as the packet payload
+    /// so that the channel and the checksum apply to it as to source symbols.
+    /// The `networkcoding` repair symbols are opaque: their payload stays in the metadata.
+    fn generate_repair_symbol(&mut self) -> Result<(ErasedMetadata, Vec<u8>)> {
+        match self {
+            #[cfg(feature = "networkcoding")]
+            Self::NetworkCoding(tart) => tart
+                .generate_and_serialize_repair_symbol()
+                .map(|repair| (ErasedMetadata::new(repair), Vec::new()))
+                .map_err(encoder_error),
+            Self::Native(tart) => {
+                let mut repair = tart
+                    .generate_and_serialize_repair_symbol()
+                    .map_err(encoder_error)?;
+                let payload = repair.take_payload();
+                Ok((ErasedMetadata::new(repair), payload))
+            }
+        }
+    }
+}
+
+/// Sliding-window decoder of the chosen backend, working on the IDs of the simulator.
+/// Returns the IDs and payloads of the recovered source symbols.
+enum CodecDecoder {
+    #[cfg(feature = "networkcoding")]
+    NetworkCoding(networkcoding::Decoder),
+
+    Native(RlcDecoder),
+}
+
+impl CodecDecoder {
+    fn new(codec: &TartCodec, max_wnd: usize) -> Result<Self> {
+        let capacity = max_wnd * codec.window_factor;
+        Ok(match codec.backend {
+            #[cfg(feature = "networkcoding")]
+            TartBackend::Rlc => Self::NetworkCoding(networkcoding::Decoder::RLC(
+                networkcoding::rlc::decoder::RLCDecoder::new(codec.symbol_size, capacity),
+            )),
+            #[cfg(feature = "networkcoding")]
+            TartBackend::Vlc => Self::NetworkCoding(networkcoding::Decoder::VLC(
+                networkcoding::vandermonde_lc::decoder::VLCDecoder::new(
+                    codec.symbol_size,
+                    capacity,
+                ),
+            )),
+            #[cfg(not(feature = "networkcoding"))]
+            TartBackend::Rlc | TartBackend::Vlc => return Err(codec.unavailable()),
+            TartBackend::Native => Self::Native(RlcDecoder::new(capacity)),
+        })
+    }
+
+    fn recv_source(
+        &mut self,
+        metadata: SourceSymbolMetadata,
+        data: Vec<u8>,
+    ) -> Result<Vec<(u64, Vec<u8>)>> {
+        match self {
+            #[cfg(feature = "networkcoding")]
+            Self::NetworkCoding(tart) => tart
+                .receive_source_symbol(
+                    networkcoding::SourceSymbol::new(metadata, data),
+                    Instant::now(),
+                )
+                .map(|symbols| symbols.iter().map(networkcoding_symbol).collect())
+                .map_err(|e| Error::FecDecoder(format!("{:?}", e))),
+            Self::Native(tart) => tart
+                .receive_source_symbol(SourceSymbol::new(metadata, data), Instant::now())
+                .map(|symbols| symbols.iter().map(recovered_symbol).collect())
+                .map_err(|e| Error::FecDecoder(format!("{:?}", e))),
+        }
+    }
+
+    fn remove_up_to(&mut self, id: u64) {
+        match self {
+            #[cfg(feature = "networkcoding")]
+            Self::NetworkCoding(tart) => {
+                tart.remove_up_to(networkcoding::source_symbol_metadata_from_u64(id), None)
+            }
+            Self::Native(tart) => tart.remove_up_to(source_symbol_metadata_from_u64(id), None),
+        }
+    }
+
+    fn recv_repair(&mut self, pkt: &Packet) -> Result<Vec<(u64, Vec<u8>)>> {
+        match self {
+            #[cfg(feature = "networkcoding")]
+            Self::NetworkCoding(tart) => {
+                let repair = pkt
+                    .repair_metadata::<networkcoding::RepairSymbol>()
+                    .ok_or(Error::FecWrongMetadata)?;
+                match tart.receive_and_deserialize_repair_symbol(repair.to_owned()) {
+                    Ok((_, symbols)) => Ok(symbols.iter().map(networkcoding_symbol).collect()),
+                    Err(networkcoding::DecoderError::UnusedRepairSymbol) => {
+                        Err(Error::UnusedRepair)
+                    }
+                    Err(e) => Err(Error::FecDecoder(format!("{:?}", e))),
+                }
+            }
+            Self::Native(tart) => {
+                let mut repair = pkt
+                    .repair_metadata::<RepairSymbol>()
+                    .ok_or(Error::FecWrongMetadata)?
+                    .to_owned();
+                repair.set_payload(pkt.data.clone());
+                match tart.receive_and_deserialize_repair_symbol(repair) {
+                    Ok((_, symbols)) => Ok(symbols.iter().map(recovered_symbol).collect()),
+                    Err(DecoderError::UnusedRepairSymbol) => Err(Error::UnusedRepair),
+                    Err(e) => Err(Error::FecDecoder(format!("{:?}", e))),
+                }
+            }
+        }
+    }
+}
+
+fn recovered_symbol(symbol: &SourceSymbol) -> (u64, Vec<u8>) {
+    (source_symbol_metadata_to_u64(symbol.metadata()), symbol.get().to_owned())
+}
+
+#[cfg(feature = "networkcoding")]
+fn networkcoding_symbol(symbol: &networkcoding::SourceSymbol) -> (u64, Vec<u8>) {
+    let id = networkcoding::source_symbol_metadata_to_u64(symbol.metadata());
+    (id, symbol.get().to_owned())
+}
+
 pub struct TartEncoder {
-    tart: Encoder,
+    tart: CodecEncoder,
+
+    codec: TartCodec,
 
     scheduler: Box<dyn TartFecScheduler>,
 
     max_wnd: usize,
+
+    /// Virtual time of the last protected source symbol.
+    last_time: Duration,
+
+    /// Idle virtual time after which the last source symbols are protected (tail protection).
+    idle_timeout: Option<Duration>,
+
+    /// Whether the last source symbols are protected at the end of the stream.
+    flush_end: bool,
+
+    /// Number of repair symbols generated by the scheduler after a source symbol.
+    nb_rs: u64,
+
+    /// Number of tail repair symbols generated after an idle timeout.
+    nb_idle_rs: u64,
+
+    /// Number of tail repair symbols generated at the end of the stream.
+    nb_end_rs: u64,
+
+    /// Source symbols with a lower ID have been removed from the window.
+    window_start: u64,
+
+    /// Sum of the window sizes when each source symbol is protected.
+    sum_window: u64,
+
+    /// Largest window size when a source symbol is protected.
+    max_window_seen: u64,
+
+    /// Trace recording the window size when each source symbol is protected.
+    window_trace: Option<Vec<u64>>,
+
+    /// Source symbols with a lower ID were received or recovered by the decoder.
+    acked_up_to: u64,
+
+    /// Number of repair symbols asked by the scheduler but not sent, the whole window being acked.
+    nb_skipped_rs: u64,
 }
 
-impl TartEncoder {
-    pub fn protect_symbol(&mut self, pkt: &mut Packet) -> Result<()> {
-        let mut next_metadata = self.tart.next_metadata().unwrap();
-        self.tart
-            .protect_data(pkt.data.clone(), &mut next_metadata)
-            .unwrap();
-        pkt.add_fec_metadata(FecMetadata::Source(FecSourceMetadata::Tart(next_metadata)))?;
-        if self.tart.n_protected_symbols() >= self.max_wnd {
+impl FecEncoderScheme for TartEncoder {
+    fn protect_symbol(&mut self, pkt: &mut Packet) -> Result<()> {
+        let next_metadata = self.tart.protect(pkt.data.clone())?;
+        pkt.add_fec_metadata(FecMetadata::Source(FecSourceMetadata::new(next_metadata)))?;
+        self.last_time = pkt.time;
+        self.reset();
+
+        let window = self.tart.n_protected_symbols() as u64;
+        self.sum_window += window;
+        self.max_window_seen = self.max_window_seen.max(window);
+        if let Some(trace) = self.window_trace.as_mut() {
+            trace.push(window);
+        }
+        Ok(())
+    }
+
+    fn should_generate_rs(&mut self) -> bool {
+        self.should_send_rs()
+    }
+
+    fn generate_rs(&mut self) -> Result<Vec<Packet>> {
+        let mut out = Vec::new();
+
+        let current_id = self.next_id();
+        while self.scheduler.should_generate_rs(current_id) {
+            if self.tart.n_protected_symbols() > 0 {
+                out.push(self.repair()?);
+            } else {
+                self.nb_skipped_rs += 1;
+            }
+            self.on_sent_rs();
+        }
+        self.nb_rs += out.len() as u64;
+
+        Ok(out)
+    }
+
+    fn recv_feedback(&mut self, feedback: &FeedbackReport) {
+        self.scheduler.recv_feedback(feedback);
+
+        // The receiver does not need repair symbols for the acknowledged source symbols.
+        if let Some(id) = feedback.get_highest_in_order() {
+            self.acked_up_to = self.acked_up_to.max(id + 1);
             self.reset();
         }
+    }
 
-        Ok(())
+    fn on_timer(&mut self, now: Duration) -> Result<Vec<Packet>> {
+        match self.idle_timeout {
+            Some(timeout) if now.saturating_sub(self.last_time) >= timeout => {
+                let out = self.generate_tail_rs()?;
+                self.nb_idle_rs += out.len() as u64;
+                Ok(out)
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn flush(&mut self) -> Result<Vec<Packet>> {
+        if !self.flush_end {
+            return Ok(Vec::new());
+        }
+        let out = self.generate_tail_rs()?;
+        self.nb_end_rs += out.len() as u64;
+        Ok(out)
+    }
+
+    fn stats(&self) -> FecStats {
+        vec![
+            ("repairs".to_string(), self.nb_rs),
+            ("idle-tail-repairs".to_string(), self.nb_idle_rs),
+            ("end-tail-repairs".to_string(), self.nb_end_rs),
+            ("mean-window".to_string(), self.get_mean_window().round() as u64),
+            ("max-window".to_string(), self.max_window_seen),
+            ("skipped-repairs".to_string(), self.nb_skipped_rs),
+        ]
     }
 
+    fn name(&self) -> String {
+        format!("{:?}", self)
+    }
+}
+
+impl TartEncoder {
     pub fn next_id(&mut self) -> u64 {
-        source_symbol_metadata_to_u64(self.tart.next_metadata().unwrap())
+        self.tart.next_id()
     }
 
     pub fn should_send_rs(&mut self) -> bool {
@@ -57,56 +493,119 @@ impl TartEncoder {
         self.scheduler.on_sent_rs(next_id)
     }
 
+    /// Repair symbol over the current window.
+    fn repair(&mut self) -> Result<Packet> {
+        let (repair, data) = self.tart.generate_repair_symbol()?;
+        Ok(Packet {
+            id: self.next_id(),
+            fec: Some(FecMetadata::Repair(repair)),
+            recovered: None,
+            data,
+            ..Default::default()
+        })
+    }
+
+    /// Tail repair symbols: as many as the scheduler asks for the symbols since the last repair symbol.
+    fn generate_tail_rs(&mut self) -> Result<Vec<Packet>> {
+        if self.tart.n_protected_symbols() == 0 {
+            return Ok(Vec::new());
+        }
+        let current_id = self.next_id();
+        let out = (0..self.scheduler.nb_tail_rs(current_id))
+            .map(|_| self.repair())
+            .collect::<Result<Vec<Packet>>>()?;
+        if !out.is_empty() {
+            self.on_sent_rs();
+        }
+        Ok(out)
+    }
+
+    /// Protects the last source symbols after `timeout` of virtual time without a new source symbol.
+    pub fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.idle_timeout = timeout;
+    }
+
+    /// Protects the last source symbols at the end of the stream.
+    pub fn set_flush_end(&mut self, flush_end: bool) {
+        self.flush_end = flush_end;
+    }
+
+    /// Number of tail repair symbols generated, after an idle timeout or at the end of the stream.
+    pub fn get_nb_tail_rs(&self) -> u64 {
+        self.nb_idle_rs + self.nb_end_rs
+    }
+
+    /// Slides the window up to the ID given by the scheduler, within the coding window capacity,
+    /// and past the source symbols acknowledged by the decoder.
     pub fn reset(&mut self) {
         let next_id = self.next_id();
-        let id_to_remove = next_id.saturating_sub(self.max_wnd as u64);
-        if id_to_remove > 0 {
-            self.tart
-                .remove_up_to(source_symbol_metadata_from_u64(id_to_remove));
+        let capacity = (self.max_wnd * self.codec.window_factor) as u64;
+        let id_to_remove = source_symbol_metadata_to_u64(self.scheduler.should_reset_up_to(next_id))
+            .max(next_id.saturating_sub(capacity))
+            .max(self.acked_up_to.min(next_id));
+        if id_to_remove > self.window_start {
+            self.tart.remove_up_to(id_to_remove);
+            self.window_start = id_to_remove;
         }
     }
 
-    pub fn generate_rs(&mut self) -> Result<Vec<Packet>> {
-        let mut out = Vec::new();
+    pub fn activate_window_trace(&mut self) {
+        self.window_trace = Some(Vec::new())
+    }
 
-        let current_id = self.next_id();
-        while self.scheduler.should_generate_rs(current_id) {
-            let repair = self
-                .tart
-                .generate_and_serialize_repair_symbol()
-                .map_err(|e| Error::FecEncoder(format!("{:?}", e).to_string()))?;
-            let rs = Packet {
-                id: current_id,
-                fec: Some(FecMetadata::Repair(FecRepairMetadata::Tart(repair))),
-                recovered: None,
-                data: Vec::new(),
-            };
-            out.push(rs);
-            self.on_sent_rs();
-        }
+    pub fn get_window_trace(&self) -> Option<&[u64]> {
+        self.window_trace.as_deref()
+    }
 
-        Ok(out)
+    /// Mean number of source symbols in the window when a source symbol is protected.
+    pub fn get_mean_window(&self) -> f64 {
+        let nb_protected = self.tart.next_id();
+        if nb_protected == 0 {
+            return 0.0;
+        }
+        self.sum_window as f64 / nb_protected as f64
     }
 
+    /// Encoder using the default backend.
     pub fn new(scheduler: Box<dyn TartFecScheduler>, max_wnd: u64) -> Self {
-        Self {
-            #[cfg(feature = "rlc")]
-            tart: Encoder::RLC(RLCEncoder::new(8, max_wnd as usize * 10, 1)),
-            #[cfg(not(feature = "rlc"))]
-            tart: Encoder::VLC(VLCEncoder::new(8, max_wnd as usize * MAX_WINDOW_FACTOR)),
+        Self::new_with_codec(scheduler, max_wnd, TartCodec::default())
+            .expect("The default backend is always available")
+    }
+
+    /// Encoder using the given backend. Fails if the backend is not compiled.
+    pub fn new_with_codec(
+        scheduler: Box<dyn TartFecScheduler>,
+        max_wnd: u64,
+        codec: TartCodec,
+    ) -> Result<Self> {
+        Ok(Self {
+            tart: CodecEncoder::new(&codec, max_wnd as usize)?,
+            codec,
             scheduler,
             max_wnd: max_wnd as usize,
-        }
+            last_time: Duration::ZERO,
+            idle_timeout: None,
+            flush_end: false,
+            nb_rs: 0,
+            nb_idle_rs: 0,
+            nb_end_rs: 0,
+            window_start: 0,
+            sum_window: 0,
+            max_window_seen: 0,
+            window_trace: None,
+            acked_up_to: 0,
+            nb_skipped_rs: 0,
+        })
     }
 
-    pub fn recv_feedback(&mut self, nb_lost: u64, nb_elems: u64) {
-        self.scheduler.recv_feedback(nb_lost, nb_elems);
+    pub fn get_codec(&self) -> &TartCodec {
+        &self.codec
     }
 }
 
 impl Debug for TartEncoder {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "tart_{:?}", self.scheduler)
+        write!(f, "tart_{:?}_{:?}", self.codec, self.scheduler)
     }
 }
 
@@ -117,82 +616,193 @@ pub trait TartFecScheduler: Debug {
 
     fn should_reset_up_to(&mut self, current: u64) -> SourceSymbolMetadata;
 
-    fn recv_feedback(&mut self, nb_lost: u64, nb_elems: u64);
+    fn recv_feedback(&mut self, feedback: &FeedbackReport);
+
+    /// Number of repair symbols protecting the source symbols sent since the last repair symbol,
+    /// when no source symbol follows them.
+    fn nb_tail_rs(&self, current: u64) -> u64;
 }
 
 pub struct TartDecoder {
-    tart: Decoder,
+    tart: CodecDecoder,
+
+    codec: TartCodec,
 
     max_window: u64,
+
+    /// Distance to the newest source symbol after which a source symbol is evicted.
+    eviction_window: u64,
+
+    /// Source symbols with a lower ID have been evicted.
+    evicted_up_to: u64,
+
+    /// IDs of the source symbols received or recovered that are not evicted yet.
+    known: BTreeSet<u64>,
+
+    /// Number of source symbols evicted while still missing, i.e., now unrecoverable.
+    nb_evicted_missing: u64,
 }
 
-impl TartDecoder {
-    pub fn recv_ss(&mut self, pkt: &Packet) -> Result<Vec<Packet>> {
-        if let Some(FecMetadata::Source(FecSourceMetadata::Tart(metadata))) = pkt.fec {
-            let id = source_symbol_metadata_to_u64(metadata);
-            let id_to_remove = id.saturating_sub(self.max_window * 2);
-            if id_to_remove > 0 {
-                // self.tart
-                //     .remove_up_to(source_symbol_metadata_from_u64(id_to_remove), None);
+impl FecDecoderScheme for TartDecoder {
+    fn recv_ss(&mut self, pkt: &Packet) -> Result<Vec<Packet>> {
+        if let Some(&metadata) = pkt.source_metadata::<SourceSymbolMetadata>() {
+            // Source symbols and packets are numbered in the same order: same window for both.
+            let ssid = source_symbol_metadata_to_u64(metadata);
+            if ssid >= self.eviction_window {
+                self.tart.remove_up_to(ssid - self.eviction_window);
+                self.evict_up_to(pkt.id.saturating_sub(self.eviction_window));
             }
-
-            let source_symbol = SourceSymbol::new(metadata, pkt.data.clone());
-            match self
-                .tart
-                .receive_source_symbol(source_symbol, Instant::now())
-            {
-                Err(e) => Err(Error::FecDecoder(format!("{:?}", e).to_string())),
-                Ok(decoded_symbols) => Ok(decoded_symbols
-                    .iter()
-                    .map(|symbol| {
-                        Packet::new_recovered(
-                            u64::from_be_bytes(symbol.get().to_owned().try_into().unwrap()),
-                            pkt.id,
-                        )
-                    })
-                    .collect()),
+            if pkt.id >= self.evicted_up_to {
+                self.known.insert(pkt.id);
             }
+
+            let recovered = self.tart.recv_source(metadata, pkt.data.clone())?;
+            Ok(self.recovered(recovered, pkt.id))
         } else {
             Err(Error::FecWrongMetadata)
         }
     }
 
-    pub fn recv_rs(&mut self, pkt: &Packet) -> Result<Vec<Packet>> {
-        if let Some(FecMetadata::Repair(FecRepairMetadata::Tart(repair_symbol))) = &pkt.fec {
-            match self
-                .tart
-                .receive_and_deserialize_repair_symbol(repair_symbol.to_owned())
-                .map(|(_, recovered_symbols)| {
-                    recovered_symbols
-                        .iter()
-                        .map(|symbol| {
-                            Packet::new_recovered(
-                                u64::from_be_bytes(symbol.get().to_owned().try_into().unwrap()),
-                                pkt.id,
-                            )
-                        })
-                        .collect()
-                }) {
-                Ok(v) => Ok(v),
-                Err(networkcoding::DecoderError::UnusedRepairSymbol) => Err(Error::UnusedRepair),
-                Err(e) => Err(Error::FecDecoder(format!("{:?}", e).to_string())),
-            }
-        } else {
-            Err(Error::FecWrongMetadata)
-        }
+    fn recv_rs(&mut self, pkt: &Packet) -> Result<Vec<Packet>> {
+        let recovered = self.tart.recv_repair(pkt)?;
+        Ok(self.recovered(recovered, pkt.id))
+    }
+
+    fn stats(&self) -> FecStats {
+        vec![("evicted-missing".to_string(), self.nb_evicted_missing)]
     }
 
+    fn name(&self) -> String {
+        format!("tart_{:?}_{}", self.codec, self.max_window)
+    }
+}
+
+impl TartDecoder {
+    /// Decoder using the default backend.
     pub fn new(max_wnd: u64) -> Self {
-        Self {
-            #[cfg(feature = "rlc")]
-            tart: Decoder::RLC(RLCDecoder::new(8, max_wnd as usize * 10)),
-            #[cfg(not(feature = "rlc"))]
-            tart: Decoder::VLC(VLCDecoder::new(8, max_wnd as usize * MAX_WINDOW_FACTOR)),
+        Self::new_with_codec(max_wnd, TartCodec::default())
+            .expect("The default backend is always available")
+    }
+
+    /// Decoder using the given backend. Fails if the backend is not compiled.
+    pub fn new_with_codec(max_wnd: u64, codec: TartCodec) -> Result<Self> {
+        Ok(Self {
+            tart: CodecDecoder::new(&codec, max_wnd as usize)?,
+            codec,
             max_window: max_wnd,
+            eviction_window: max_wnd * 2,
+            evicted_up_to: 0,
+            known: BTreeSet::new(),
+            nb_evicted_missing: 0,
+        })
+    }
+
+    /// Evicts the source symbols older than `window` source symbols before the newest one
+    /// (default: twice the maximum FEC window). Evicted source symbols cannot be recovered anymore.
+    pub fn set_eviction_window(&mut self, window: u64) {
+        self.eviction_window = window.max(1);
+    }
+
+    /// Number of source symbols evicted while still missing.
+    pub fn get_nb_evicted_missing(&self) -> u64 {
+        self.nb_evicted_missing
+    }
+
+    /// Forgets the source symbols with a lower ID, counting the ones never received nor recovered.
+    fn evict_up_to(&mut self, id: u64) {
+        if id <= self.evicted_up_to {
+            return;
         }
+        let kept = self.known.split_off(&id);
+        let nb_known = std::mem::replace(&mut self.known, kept).len() as u64;
+        self.nb_evicted_missing += id - self.evicted_up_to - nb_known;
+        self.evicted_up_to = id;
+    }
+
+    /// Packets of the recovered source symbols, recovered thanks to the packet `from`.
+    /// Source symbols and packets have the same IDs.
+    fn recovered(&mut self, symbols: Vec<(u64, Vec<u8>)>, from: u64) -> Vec<Packet> {
+        let recovered: Vec<Packet> = symbols
+            .into_iter()
+            .map(|(id, data)| {
+                let mut pkt = Packet::new_recovered(id, from);
+                pkt.data = data;
+                pkt
+            })
+            .collect();
+        let evicted_up_to = self.evicted_up_to;
+        self.known.extend(recovered.iter().map(|pkt| pkt.id).filter(|&id| id >= evicted_up_to));
+        recovered
     }
 }
 
+/// Builds a TART encoder and decoder from the parameters:
+/// - `window`: maximum FEC window (default: 100);
+/// - `feedback`: number of source symbols between two feedbacks (default: 500);
+/// - `scheduler`: `adaptive` (default), `burst` or `window`;
+/// - `step`: step between two repair symbols of the `window` scheduler (default: 10);
+/// - `alpha`, `beta`: parameters of the `adaptive` and `burst` schedulers (default: 0.9 and 1.0);
+/// - `initial-loss`: initial loss estimation of the `adaptive` and `burst` schedulers
+///   (default: none);
+/// - `backend`: `rlc`, `vlc` or `native` (default: see [`TartBackend::default`]);
+/// - `symbol-size`: symbol size of the `networkcoding` backends (default: 8);
+/// - `window-factor`: capacity of the coding window, relative to `window` (default: depends on the backend);
+/// - `idle-timeout-us`: idle virtual time after which the last source symbols are protected (default: never);
+/// - `flush-end`: whether the last source symbols are protected at the end of the stream (default: false);
+/// - `eviction-window`: distance to the newest source symbol after which the decoder evicts
+///   a source symbol (default: twice `window`);
+/// - `ack`: whether the feedback acknowledges the source symbols received or recovered in order,
+///   or evicted by the decoder, so that the encoder removes them from its window (default: false);
+/// - `feedback-encoding`: loss pattern of the feedback, `bitmap` (default), `rle` or `summary`.
+pub fn from_params(params: &FecParams) -> Result<(EncoderNode, DecoderNode)> {
+    let window = params.get_or("window", 100)?;
+    let scheduler: Box<dyn TartFecScheduler> =
+        match params.get_or("scheduler", "adaptive".to_string())?.as_str() {
+            "window" => Box::new(WindowStepScheduler::new(window, params.get_or("step", 10)?)),
+            "burst" => {
+                let mut scheduler = BurstFecScheduler::new(params.get_or("alpha", 0.9)?, window);
+                if let Some(loss) = params.get("initial-loss")? {
+                    scheduler.set_initial_loss_estimation(loss);
+                }
+                scheduler.set_beta_fec(params.get_or("beta", 1.0)?);
+                Box::new(scheduler)
+            }
+            _ => {
+                let mut scheduler = AdaptiveFecScheduler::new(params.get_or("alpha", 0.9)?, window);
+                if let Some(loss) = params.get("initial-loss")? {
+                    scheduler.set_initial_loss_estimation(loss);
+                }
+                scheduler.set_beta_fec(params.get_or("beta", 1.0)?);
+                Box::new(scheduler)
+            }
+        };
+    let backend = match params.get::<String>("backend")? {
+        Some(backend) => TartBackend::try_from(backend.as_str())?,
+        None => TartBackend::default(),
+    };
+    let mut codec = TartCodec::new(backend);
+    codec.set_symbol_size(params.get_or("symbol-size", 8)?);
+    codec.set_window_factor(params.get_or("window-factor", backend.default_window_factor())?);
+
+    let mut encoder = TartEncoder::new_with_codec(scheduler, window, codec.clone())?;
+    encoder.set_idle_timeout(params.get("idle-timeout-us")?.map(Duration::from_micros));
+    encoder.set_flush_end(params.get_or("flush-end", false)?);
+    let encoder = EncoderNode::new(Box::new(encoder));
+
+    let eviction_window = params.get_or("eviction-window", window * 2)?;
+    let mut fec_decoder = Box::new(TartDecoder::new_with_codec(window, codec)?);
+    fec_decoder.set_eviction_window(eviction_window);
+    let mut feedback = DecoderFeedback::new(params.get_or("feedback", 500)?);
+    feedback.set_ack(params.get_or("ack", false)?);
+    // Evicted source symbols cannot be recovered anymore.
+    feedback.set_ack_window(eviction_window);
+    let encoding = params.get_or("feedback-encoding", String::new())?;
+    feedback.set_encoding(LossEncoding::from(encoding.as_str()));
+    let decoder = DecoderNode::new(fec_decoder, Some(feedback));
+
+    Ok((encoder, decoder))
+}
+
 pub struct WindowStepScheduler {
     /// Maximum number of symbols in the window.
     max_wnd: u64,
@@ -217,7 +827,12 @@ impl TartFecScheduler for WindowStepScheduler {
         source_symbol_metadata_from_u64(current.saturating_sub(self.max_wnd))
     }
 
-    fn recv_feedback(&mut self, _nb_lost: u64, _nb_elems: u64) {}
+    fn recv_feedback(&mut self, _feedback: &FeedbackReport) {}
+
+    /// A single repair symbol, as for a complete step.
+    fn nb_tail_rs(&self, current: u64) -> u64 {
+        (current > self.last_sent) as u64
+    }
 }
 
 impl Debug for WindowStepScheduler {
@@ -264,19 +879,10 @@ impl Debug for AdaptiveFecScheduler {
 
 impl TartFecScheduler for AdaptiveFecScheduler {
     fn should_generate_rs(&self, current: u64) -> bool {
-        // Generate enough repair symbols to alleviate the loss percentage estimated by the feedback.
-        // Spread these repair symbols.
-        if self.loss_estimation == 0.0 {
-            return false;
+        match self.repair_interval() {
+            Some(next_rs) => current.saturating_sub(self.last_sent_ssid) as f64 >= next_rs,
+            None => false,
         }
-
-        let nb_lost_pkt_per_window = (self.loss_estimation
-            + self.beta * self.loss_variance_estimation)
-            * self.wsize as f64
-            * self.beta;
-
-        let next_rs = self.wsize as f64 / nb_lost_pkt_per_window;
-        current.saturating_sub(self.last_sent_ssid) as f64 >= next_rs
     }
 
     fn on_sent_rs(&mut self, current: u64) {
@@ -287,11 +893,11 @@ impl TartFecScheduler for AdaptiveFecScheduler {
         source_symbol_metadata_from_u64(current.saturating_sub(self.wsize))
     }
 
-    fn recv_feedback(&mut self, nb_lost: u64, nb_elems: u64) {
-        if nb_elems == 0 {
+    fn recv_feedback(&mut self, feedback: &FeedbackReport) {
+        if feedback.get_nb_elems() == 0 {
             return;
         }
-        let local_loss = nb_lost as f64 / nb_elems as f64;
+        let local_loss = feedback.get_nb_lost() as f64 / feedback.get_nb_elems() as f64;
         let local_variance = (self.loss_estimation - local_loss).abs();
         self.loss_estimation = self.loss_estimation * self.alpha + (1.0 - self.alpha) * local_loss;
         self.loss_variance_estimation =
@@ -305,9 +911,35 @@ impl TartFecScheduler for AdaptiveFecScheduler {
             self.loss_variance_estimation, local_variance
         );
     }
+
+    /// As many repair symbols as the scheduler would send over these source symbols, rounded up.
+    fn nb_tail_rs(&self, current: u64) -> u64 {
+        match self.repair_interval() {
+            Some(next_rs) => {
+                (current.saturating_sub(self.last_sent_ssid) as f64 / next_rs).ceil() as u64
+            }
+            None => 0,
+        }
+    }
 }
 
 impl AdaptiveFecScheduler {
+    /// Number of source symbols between two repair symbols, if any.
+    fn repair_interval(&self) -> Option<f64> {
+        // Generate enough repair symbols to alleviate the loss percentage estimated by the feedback.
+        // Spread these repair symbols.
+        if self.loss_estimation == 0.0 {
+            return None;
+        }
+
+        let nb_lost_pkt_per_window = (self.loss_estimation
+            + self.beta * self.loss_variance_estimation)
+            * self.wsize as f64
+            * self.beta;
+
+        Some(self.wsize as f64 / nb_lost_pkt_per_window)
+    }
+
     pub fn new(alpha: f64, wsize: u64) -> Self {
         Self {
             loss_estimation: 0.0,
@@ -331,3 +963,158 @@ impl AdaptiveFecScheduler {
         self.alpha = alpha;
     }
 }
+
+/// Adaptive scheduler aware of the bursts of losses. Besides the loss rate, it estimates the length
+/// of the bursts from the feedback, and sends groups of back-to-back repair symbols large enough to
+/// recover a burst, spaced to match the loss rate while the burst is still in the window.
+pub struct BurstFecScheduler {
+    /// Estimated mean loss rate based on feedback.
+    loss_estimation: f64,
+
+    /// Variance of the loss estimation.
+    loss_variance_estimation: f64,
+
+    /// Estimated mean length of the bursts of losses.
+    burst_estimation: f64,
+
+    /// Variance of the burst length estimation.
+    burst_variance_estimation: f64,
+
+    /// Learning parameter for the moving averages.
+    alpha: f64,
+
+    /// Tweaking parameter to increase redundancy ratio.
+    beta: f64,
+
+    /// SSID where the last group of repair symbols was sent.
+    last_sent_ssid: u64,
+
+    /// Number of repair symbols sent in the last group.
+    nb_sent_in_group: u64,
+
+    /// Maximum window size
+    wsize: u64,
+}
+
+impl Debug for BurstFecScheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "burst_{}_{}_{}", self.alpha, self.beta, self.wsize)
+    }
+}
+
+impl TartFecScheduler for BurstFecScheduler {
+    fn should_generate_rs(&self, current: u64) -> bool {
+        if self.loss_estimation == 0.0 {
+            return false;
+        }
+        if current == self.last_sent_ssid {
+            return self.nb_sent_in_group < self.get_group_size();
+        }
+        current.saturating_sub(self.last_sent_ssid) as f64 >= self.get_spacing()
+    }
+
+    fn on_sent_rs(&mut self, current: u64) {
+        if current == self.last_sent_ssid {
+            self.nb_sent_in_group += 1;
+        } else {
+            self.last_sent_ssid = current;
+            self.nb_sent_in_group = 1;
+        }
+    }
+
+    fn should_reset_up_to(&mut self, current: u64) -> SourceSymbolMetadata {
+        source_symbol_metadata_from_u64(current.saturating_sub(self.wsize))
+    }
+
+    fn recv_feedback(&mut self, feedback: &FeedbackReport) {
+        if feedback.get_nb_elems() == 0 {
+            return;
+        }
+        let local_loss = feedback.get_nb_lost() as f64 / feedback.get_nb_elems() as f64;
+        let local_variance = (self.loss_estimation - local_loss).abs();
+        self.loss_estimation = self.loss_estimation * self.alpha + (1.0 - self.alpha) * local_loss;
+        self.loss_variance_estimation =
+            self.loss_variance_estimation * self.alpha + (1.0 - self.alpha) * local_variance;
+
+        // Without losses, the feedback says nothing about the bursts.
+        if feedback.get_nb_lost() == 0 {
+            return;
+        }
+
+        // Without the loss pattern, the longest burst is the only hint.
+        let bursts = feedback.get_bursts();
+        let local_burst = if bursts.is_empty() {
+            feedback.get_max_burst() as f64
+        } else {
+            bursts.iter().sum::<u64>() as f64 / bursts.len() as f64
+        };
+        let local_variance = (self.burst_estimation - local_burst).abs();
+        self.burst_estimation =
+            self.burst_estimation * self.alpha + (1.0 - self.alpha) * local_burst;
+        self.burst_variance_estimation =
+            self.burst_variance_estimation * self.alpha + (1.0 - self.alpha) * local_variance;
+        info!(
+            "New burst estimation: {} from local burst estimation: {}",
+            self.burst_estimation, local_burst
+        );
+    }
+
+    /// Whole groups of repair symbols, as many as the scheduler would send over these symbols.
+    fn nb_tail_rs(&self, current: u64) -> u64 {
+        if self.loss_estimation == 0.0 {
+            return 0;
+        }
+        let nb_symbols = current.saturating_sub(self.last_sent_ssid) as f64;
+        let nb_groups = (nb_symbols / self.get_spacing()).ceil();
+        nb_groups as u64 * self.get_group_size()
+    }
+}
+
+impl BurstFecScheduler {
+    pub fn new(alpha: f64, wsize: u64) -> Self {
+        Self {
+            loss_estimation: 0.0,
+            loss_variance_estimation: 0.0,
+            burst_estimation: 1.0,
+            burst_variance_estimation: 0.0,
+            alpha,
+            beta: 1.0,
+            last_sent_ssid: 0,
+            nb_sent_in_group: 0,
+            wsize,
+        }
+    }
+
+    pub fn set_initial_loss_estimation(&mut self, loss: f64) {
+        self.loss_estimation = loss;
+    }
+
+    pub fn set_beta_fec(&mut self, beta: f64) {
+        self.beta = beta;
+    }
+
+    pub fn set_alpha_fec(&mut self, alpha: f64) {
+        self.alpha = alpha;
+    }
+
+    /// Number of back-to-back repair symbols, enough to recover a burst of the expected length.
+    pub fn get_group_size(&self) -> u64 {
+        let burst = self.beta * (self.burst_estimation + self.burst_variance_estimation);
+        (burst.ceil() as u64).clamp(1, self.wsize.max(1))
+    }
+
+    /// Number of source symbols between two groups of repair symbols, and at most the window minus a
+    /// burst. The repair symbols of a window cover its expected losses, and their deviation: with
+    /// bursts of length `b`, the losses of a window come from `L / b` bursts, hence a deviation of
+    /// `sqrt(L * b)`.
+    pub fn get_spacing(&self) -> f64 {
+        let loss = self.loss_estimation + self.beta * self.loss_variance_estimation;
+        let nb_lost_pkt_per_window = loss * self.wsize as f64;
+        let burst = self.burst_estimation + self.burst_variance_estimation;
+        let nb_rs_per_window =
+            self.beta * (nb_lost_pkt_per_window + (nb_lost_pkt_per_window * burst).sqrt());
+        let max_spacing = self.wsize.saturating_sub(self.get_group_size()).max(1) as f64;
+        let spacing = self.get_group_size() as f64 * self.wsize as f64 / nb_rs_per_window;
+        spacing.clamp(1.0, max_spacing)
+    }
+}
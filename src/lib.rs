@@ -3,6 +3,7 @@ extern crate log;
 
 use std::hash::Hash;
 use std::hash::Hasher;
+use std::time::Duration;
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -18,11 +19,11 @@ pub enum Error {
 
     FecWrongMetadata,
 
-    FeedbackIdTooBig,
-
     UnusedRepair,
 
     TooOldEquation,
+
+    UnknownFecScheme(String),
 }
 
 #[derive(Default, Clone, Debug)]
@@ -33,6 +34,12 @@ pub struct Packet {
     recovered: Option<u64>, // Distance from its ID where it has been recovered.
 
     data: Vec<u8>,
+
+    /// Virtual time at which the packet is transmitted on the link.
+    time: Duration,
+
+    /// Checksum of the payload in the simulated HIRT header, with the algorithm used to compute it.
+    checksum: Option<(Checksum, u32)>,
 }
 
 impl Packet {
@@ -50,6 +57,22 @@ impl Packet {
         pkt.recovered = Some(from.saturating_sub(id));
         pkt
     }
+
+    pub fn get_time(&self) -> Duration {
+        self.time
+    }
+
+    /// Adds the checksum of the payload in the header.
+    pub fn add_checksum(&mut self, checksum: Checksum) {
+        self.checksum = Some((checksum, checksum.compute(&self.data)));
+    }
+
+    /// Whether the payload matches the checksum of the header. Always true without checksum.
+    pub fn verify_checksum(&self) -> bool {
+        self.checksum
+            .map(|(checksum, value)| checksum.compute(&self.data) == value)
+            .unwrap_or(true)
+    }
 }
 
 impl PartialEq for Packet {
@@ -78,11 +101,32 @@ pub struct Simulator {
     /// Dropper.
     dropper: Dropper,
 
+    /// Bit-error channel.
+    corrupter: Corrupter,
+
     /// Decoder.
     decoder: Decoder,
 
     /// Sink node.
     sink: Sink,
+
+    /// Current virtual time.
+    now: Duration,
+
+    /// Virtual time between two packets generated by the source.
+    interval: Duration,
+
+    /// Time to transmit a packet on the link. Packets generated at the same time are sent back-to-back.
+    tx_time: Duration,
+
+    /// Virtual time at which the link is free to transmit the next packet.
+    link_free: Duration,
+
+    /// Number of packets of a traffic burst of the source, followed by `off_time` of silence.
+    on_packets: Option<u64>,
+
+    /// Silence of the source after each traffic burst.
+    off_time: Duration,
 }
 
 impl Simulator {
@@ -91,37 +135,99 @@ impl Simulator {
             source: Source::new(),
             encoder: Encoder::new_simple(),
             dropper: Dropper::new_simple(),
+            corrupter: Corrupter::new_simple(),
             decoder: Decoder::new_simple(),
             sink: Sink::new(),
+            now: Duration::ZERO,
+            interval: Duration::from_millis(1),
+            tx_time: Duration::ZERO,
+            link_free: Duration::ZERO,
+            on_packets: None,
+            off_time: Duration::ZERO,
         }
     }
 
     pub fn run(&mut self, nb_packets: u64) -> Result<()> {
-        for _iter in 0..nb_packets {
-            // Generate the packet from the source.
-            let packets = vec![self.source.gen()];
+        for iter in 0..nb_packets {
+            // Repair symbols due since the previous packet.
+            let mut packets = self.encoder.on_timer(self.now)?;
 
-            self.encoder.recv(packets)?;
-            let packets = self.encoder.forw()?;
+            // Generate the packet from the source.
+            let mut pkt = self.source.gen();
+            pkt.time = self.now;
 
-            self.dropper.recv(packets)?;
-            let packets = self.dropper.forw()?;
+            self.encoder.recv(vec![pkt])?;
+            packets.extend(self.encoder.forw()?);
 
-            self.decoder.recv(packets)?;
-            let (packets, feedback) = self.decoder.forw()?;
+            self.transmit(packets)?;
+            self.now += self.interval;
 
-            // Potentially give feedback to encoder.
-            if !feedback.is_empty() {
-                self.encoder.recv_feedback(feedback);
+            if self.on_packets.is_some_and(|on| (iter + 1) % on == 0) {
+                self.idle(self.off_time)?;
             }
+        }
+
+        // Protect the tail of the stream.
+        let packets = self.encoder.flush()?;
+        self.transmit(packets)
+    }
+
+    /// Silence of the source during `duration`. The timer of the encoder still fires every interval.
+    fn idle(&mut self, duration: Duration) -> Result<()> {
+        let end = self.now + duration;
+        let step = if self.interval.is_zero() { duration } else { self.interval };
+        while self.now < end {
+            let packets = self.encoder.on_timer(self.now)?;
+            self.transmit(packets)?;
+            self.now = (self.now + step).min(end);
+        }
+        Ok(())
+    }
+
+    /// Transmits the packets sent by the encoder on the link, up to the sink.
+    fn transmit(&mut self, mut packets: Vec<Packet>) -> Result<()> {
+        for pkt in packets.iter_mut() {
+            pkt.time = self.link_free.max(self.now);
+            self.link_free = pkt.time + self.tx_time;
+        }
+
+        self.dropper.recv(packets)?;
+        let packets = self.dropper.forw()?;
 
-            // Give the ouptut packets to the sink.
-            self.sink.recv_multiple(packets);
+        self.corrupter.recv(packets)?;
+        let packets = self.corrupter.forw()?;
+
+        self.decoder.recv(packets)?;
+        let (packets, feedback) = self.decoder.forw()?;
+
+        // Potentially give feedback to encoder.
+        if !feedback.is_empty() {
+            self.encoder.recv_feedback(feedback);
         }
 
+        // Give the ouptut packets to the sink.
+        self.sink.recv_multiple(packets);
+
         Ok(())
     }
 
+    /// Sets the virtual time between two source packets, and the time to transmit a packet on the link.
+    /// Only time-based drop schedulers depend on these values.
+    pub fn set_timing(&mut self, interval: Duration, tx_time: Duration) {
+        self.interval = interval;
+        self.tx_time = tx_time;
+    }
+
+    /// The source sends traffic bursts of `on_packets` packets, each followed by `off_time` of silence.
+    pub fn set_on_off(&mut self, on_packets: u64, off_time: Duration) {
+        self.on_packets = Some(on_packets.max(1));
+        self.off_time = off_time;
+    }
+
+    pub fn get_time(&self) -> Duration {
+        self.now
+    }
+
     pub fn get_sink(&self) -> &Sink {
         &self.sink
     }
@@ -134,6 +240,10 @@ impl Simulator {
         self.dropper = dropper;
     }
 
+    pub fn set_corrupter(&mut self, corrupter: Corrupter) {
+        self.corrupter = corrupter;
+    }
+
     pub fn set_decoder(&mut self, decoder: Decoder) {
         self.decoder = decoder;
     }
@@ -146,6 +256,10 @@ impl Simulator {
         &self.dropper
     }
 
+    pub fn get_corrupter(&self) -> &Corrupter {
+        &self.corrupter
+    }
+
     pub fn get_decoder(&self) -> &Decoder {
         &self.decoder
     }
@@ -160,17 +274,37 @@ impl Default for Simulator {
 #[cfg(test)]
 mod tests {
 
+    use crate::checksum::Checksum;
+    use crate::drop::burst::{BurstDropScheduler, LengthDistribution};
     use crate::drop::constant::ConstantDropScheduler;
+    use crate::drop::fit::LossModelFit;
     use crate::drop::ge::GilbertEliotDropSheduler;
     use crate::drop::specific::SpecificDropScheduler;
+    use crate::drop::time::{OutageDropScheduler, TimeGilbertEliotDropScheduler};
     use crate::drop::uniform::UniformDropScheduler;
-    use crate::fec::maelstrom::{MaelstromDecoder, MaelstromEncoder};
-    use crate::fec::tart::{AdaptiveFecScheduler, TartDecoder, TartEncoder, WindowStepScheduler};
-    use crate::fec::FecDecoder;
-    use crate::node::decoder::{Decoder, DecoderFeedback};
+    use crate::drop::DropScheduler;
+    use crate::fec::maelstrom::{
+        parse_layering, repair_id, xor_repair, MaelstromDecoder, MaelstromEncoder, MaelstromSSID,
+        REPAIR_ID_FLAG,
+    };
+    use crate::fec::{FecDecoderScheme, FecEncoderScheme, FecMetadata, FecSourceMetadata};
+    use crate::fec::lt::{LtEncoder, LtVariant};
+    use crate::fec::rs::ReedSolomonEncoder;
+    use crate::fec::{FecParams, FecRegistry};
+    use crate::fec::tart::{
+        AdaptiveFecScheduler, BurstFecScheduler, TartBackend, TartCodec, TartDecoder, TartEncoder,
+        TartFecScheduler, WindowStepScheduler,
+    };
+    use crate::node::corrupter::Corrupter;
+    use crate::node::decoder::{Decoder, DecoderFeedback, FeedbackReport, LossEncoding};
     use crate::node::dropper::Dropper;
     use crate::node::encoder::Encoder;
+    use crate::node::Node;
+    use crate::seed::{Component, SeedHierarchy};
+    use crate::Error;
+    use crate::Packet;
     use crate::Simulator;
+    use std::time::Duration;
 
     #[test]
     fn test_sim_no_nodes() {
@@ -191,7 +325,7 @@ mod tests {
         // Add TART encoder with a WindowStepScheduler.
         let scheduler = WindowStepScheduler::new(fec_max_wnd, fec_step);
         let tart_encoder = TartEncoder::new(Box::new(scheduler), fec_max_wnd);
-        let encoder = Encoder::new(crate::fec::FecEncoder::Tart(tart_encoder));
+        let encoder = Encoder::new(Box::new(tart_encoder));
         simulator.set_encoder(encoder);
 
         // Add dropper.
@@ -200,7 +334,7 @@ mod tests {
         simulator.set_dropper(dropper);
 
         // Add TART decoder.
-        let fec_decoder = FecDecoder::Tart(TartDecoder::new(fec_max_wnd));
+        let fec_decoder = Box::new(TartDecoder::new(fec_max_wnd));
         let feedback = DecoderFeedback::new(feedback_frequency);
         let decoder = Decoder::new(fec_decoder, Some(feedback));
         simulator.set_decoder(decoder);
@@ -233,7 +367,7 @@ mod tests {
         let mut scheduler = AdaptiveFecScheduler::new(0.5, fec_max_wnd);
         scheduler.set_initial_loss_estimation(0.2);
         let tart_encoder = TartEncoder::new(Box::new(scheduler), fec_max_wnd);
-        let encoder = Encoder::new(crate::fec::FecEncoder::Tart(tart_encoder));
+        let encoder = Encoder::new(Box::new(tart_encoder));
         simulator.set_encoder(encoder);
 
         // Add dropper.
@@ -242,7 +376,7 @@ mod tests {
         simulator.set_dropper(dropper);
 
         // Add TART decoder.
-        let fec_decoder = FecDecoder::Tart(TartDecoder::new(fec_max_wnd));
+        let fec_decoder = Box::new(TartDecoder::new(fec_max_wnd));
         let feedback = DecoderFeedback::new(feedback_frequency);
         let decoder = Decoder::new(fec_decoder, Some(feedback));
         simulator.set_decoder(decoder);
@@ -273,7 +407,7 @@ mod tests {
 
         // Add encoder.
         let encoder = MaelstromEncoder::new(window, &interleaves_values);
-        let encoder = Encoder::new(crate::fec::FecEncoder::Maelstrom(encoder));
+        let encoder = Encoder::new(Box::new(encoder));
         simulator.set_encoder(encoder);
 
         // Add dropper.
@@ -283,7 +417,7 @@ mod tests {
 
         // Add decoder.
         let decoder = MaelstromDecoder::new(window * 20);
-        let decoder = Decoder::new(FecDecoder::Maelstrom(decoder), None);
+        let decoder = Decoder::new(Box::new(decoder), None);
         simulator.set_decoder(decoder);
 
         assert_eq!(simulator.run(100), Ok(()));
@@ -293,6 +427,364 @@ mod tests {
             simulator.get_dropper().get_nb_ss_dropped() as usize
         );
         assert!(!simulator.get_sink().get_recovered().is_empty());
+        assert!(simulator.get_sink().get_corrupted(100).is_empty());
+    }
+
+    #[test]
+    fn test_maelstrom_bounded_memory() {
+        let window = 8;
+        let run = |capacity: usize| {
+            let mut simulator = Simulator::new();
+            let encoder = MaelstromEncoder::new(window, &[1, 4, 8]);
+            simulator.set_encoder(Encoder::new(Box::new(encoder)));
+            let drop_scheduler = UniformDropScheduler::new(0.03, 1);
+            simulator.set_dropper(Dropper::new(Box::new(drop_scheduler)));
+            let decoder = MaelstromDecoder::new(capacity);
+            simulator.set_decoder(Decoder::new(Box::new(decoder), None));
+
+            assert_eq!(simulator.run(2000), Ok(()));
+            let mut recovered = simulator.get_sink().get_recovered();
+            recovered.sort();
+            let stats = simulator.get_decoder().get_fec_decoder().stats();
+            let max_stored = stats
+                .iter()
+                .find(|(name, _)| name == "max-stored-symbols")
+                .map(|(_, value)| *value)
+                .unwrap();
+            (recovered, max_stored)
+        };
+
+        // Losses are inside the window: the bounded decoder recovers exactly the same symbols.
+        let (unbounded, _) = run(1_000_000);
+        let (bounded, max_stored) = run(window * 20);
+        assert!(!bounded.is_empty());
+        assert_eq!(bounded, unbounded);
+        assert!(max_stored <= window as u64 * 20 + 1);
+    }
+
+    #[test]
+    fn test_maelstrom_running_xor() {
+        let mut encoder = MaelstromEncoder::new(3, &[1, 2]);
+        let mut pkts: Vec<Packet> = (0..6)
+            .map(|id| Packet {
+                id,
+                data: vec![1 << id; id as usize + 1],
+                ..Default::default()
+            })
+            .collect();
+
+        let mut repairs = Vec::new();
+        for pkt in pkts.iter_mut() {
+            encoder.protect_symbol(pkt).unwrap();
+            if encoder.should_generate_rs() {
+                repairs.extend(encoder.generate_rs().unwrap());
+            }
+        }
+
+        // Repairs are sent as soon as a bin is full, the first layer before the second one.
+        let expected = [([0, 1, 2], 0), ([0, 2, 4], 1), ([3, 4, 5], 0), ([1, 3, 5], 1)];
+        assert_eq!(repairs.len(), expected.len());
+        for (i, (repair, (ids, layer))) in repairs.iter().zip(expected).enumerate() {
+            let pkts = ids.iter().map(|&id| &pkts[id]);
+            let expected = xor_repair(pkts, repair_id(i as u64), layer);
+            assert_eq!(repair, &expected);
+        }
+    }
+
+    #[test]
+    fn test_maelstrom_flush() {
+        // The tail of the stream is only protected by flushing the partially filled bin.
+        for (params, flushed) in [("", 0), (",flush-end=true", 1)] {
+            let mut simulator = Simulator::new();
+            let params = FecParams::from(format!("window=10,layering=1{params}").as_str());
+            let (encoder, decoder) = FecRegistry::default().create("maelstrom", &params).unwrap();
+            simulator.set_encoder(encoder);
+            simulator.set_decoder(decoder);
+            let mut drop_scheduler = SpecificDropScheduler::new(100);
+            drop_scheduler.add_to_drop(&[24]); // Source symbol 22.
+            simulator.set_dropper(Dropper::new(Box::new(drop_scheduler)));
+
+            assert_eq!(simulator.run(25), Ok(()));
+            assert_eq!(simulator.get_sink().get_lost(25).len(), 1 - flushed);
+            let stats = simulator.get_encoder().get_fec_encoder().stats();
+            assert!(stats.contains(&("end-flushes".to_string(), flushed as u64)));
+        }
+
+        // A bin is flushed once its first source symbol is older than the timeout.
+        let mut encoder = MaelstromEncoder::new(10, &[1]);
+        encoder.set_flush_timeout(Some(Duration::from_millis(4)));
+        for id in 0..3 {
+            let mut pkt = Packet::new(id);
+            pkt.time = Duration::from_millis(id);
+            encoder.protect_symbol(&mut pkt).unwrap();
+            assert!(!encoder.should_generate_rs());
+        }
+        assert!(encoder.on_timer(Duration::from_millis(3)).unwrap().is_empty());
+        let repairs = encoder.on_timer(Duration::from_millis(4)).unwrap();
+        let pkts: Vec<Packet> = (0..3).map(Packet::new).collect();
+        assert_eq!(repairs, vec![xor_repair(pkts.iter(), repair_id(0), 0)]);
+        assert_eq!(encoder.get_nb_flushed_rs(), 1);
+
+        // A bin is flushed once more than `flush-inactivity` source symbols follow its first one,
+        // even if it receives a source symbol every interleave.
+        let mut encoder = MaelstromEncoder::new(10, &[1, 20]);
+        encoder.set_flush_inactivity(Some(41));
+        let mut repairs = Vec::new();
+        for id in 0..43 {
+            encoder.protect_symbol(&mut Packet::new(id)).unwrap();
+            if encoder.should_generate_rs() {
+                repairs.extend(encoder.generate_rs().unwrap());
+            }
+        }
+        let pkts: Vec<Packet> = [0, 20, 40].into_iter().map(Packet::new).collect();
+        assert_eq!(repairs.len(), 5);
+        assert_eq!(repairs[4], xor_repair(pkts.iter(), repair_id(4), 1));
+        assert_eq!(encoder.get_nb_flushed_rs(), 1);
+
+        // The deep layers are flushed during the silences of the source.
+        let mut simulator = Simulator::new();
+        let params = FecParams::from("window=10,flush-inactivity=41");
+        let (encoder, decoder) = FecRegistry::default().create("maelstrom", &params).unwrap();
+        simulator.set_encoder(encoder);
+        simulator.set_decoder(decoder);
+        simulator.set_on_off(7, Duration::from_millis(50));
+        assert_eq!(simulator.run(200), Ok(()));
+        let stats = simulator.get_encoder().get_fec_encoder().stats();
+        let flushes = stats.iter().find(|(name, _)| name == "inactivity-flushes").unwrap().1;
+        assert!(flushes > 0);
+    }
+
+    #[test]
+    fn test_maelstrom_layers() {
+        assert_eq!(parse_layering("1,20:10", 8), Ok(vec![(1, 8), (20, 10)]));
+        assert!(parse_layering("1:0", 8).is_err());
+
+        let mut simulator = Simulator::new();
+        let params = FecParams::from("window=100,layering=1:4_4:8");
+        let (encoder, decoder) = FecRegistry::default().create("maelstrom", &params).unwrap();
+        simulator.set_encoder(encoder);
+        simulator.set_decoder(decoder);
+        let drop_scheduler = UniformDropScheduler::new(0.05, 1);
+        simulator.set_dropper(Dropper::new(Box::new(drop_scheduler)));
+
+        assert_eq!(simulator.run(2000), Ok(()));
+        let encoder = simulator.get_encoder().get_fec_encoder();
+        assert_eq!(encoder.name(), "maelstrom_1x4_4x8");
+        let stats = encoder.stats();
+        assert!(stats.contains(&("layer0-repairs".to_string(), 500)));
+        assert!(stats.contains(&("layer1-repairs".to_string(), 248)));
+
+        // Each recovered source symbol is counted for a single layer.
+        let stats = simulator.get_decoder().get_fec_decoder().stats();
+        let per_layer: u64 = stats
+            .iter()
+            .filter(|(name, _)| name.ends_with("-recovered"))
+            .map(|(_, value)| value)
+            .sum();
+        assert!(per_layer > 0);
+        assert_eq!(per_layer, simulator.get_decoder().get_nb_recovered());
+    }
+
+    #[test]
+    fn test_maelstrom_gaussian_elimination() {
+        let pkts: Vec<Packet> = (0..3).map(Packet::new).collect();
+        let repairs = [
+            xor_repair(pkts.iter(), repair_id(0), 0),
+            xor_repair(pkts[..2].iter(), repair_id(1), 1),
+        ];
+
+        // Peeling needs another source symbol, while the difference of both repair symbols gives the last one.
+        for (gaussian, expected) in [(false, vec![]), (true, vec![2])] {
+            let mut decoder = MaelstromDecoder::new(100);
+            decoder.set_gaussian_elimination(gaussian);
+            let recovered: Vec<u64> = repairs
+                .iter()
+                .flat_map(|repair| decoder.recv_rs(repair).unwrap())
+                .map(|pkt| pkt.id)
+                .collect();
+            assert_eq!(recovered, expected);
+            assert_eq!(decoder.get_nb_extra_recovered(), expected.len() as u64);
+            let per_layer: u64 = decoder.get_nb_recovered_per_layer().iter().sum();
+            assert_eq!(per_layer, expected.len() as u64);
+        }
+
+        // Over bursty losses, the elimination recovers source symbols that peeling does not.
+        let run = |decoder: &str| {
+            let mut simulator = Simulator::new();
+            let params = format!("window=10,layering=1_4_8,decoder={decoder}");
+            let (encoder, decoder) = FecRegistry::default()
+                .create("maelstrom", &FecParams::from(params.as_str()))
+                .unwrap();
+            simulator.set_encoder(encoder);
+            simulator.set_decoder(decoder);
+            let drop_scheduler = GilbertEliotDropSheduler::new_simple(0.01, 0.2, 1);
+            simulator.set_dropper(Dropper::new(Box::new(drop_scheduler)));
+            assert_eq!(simulator.run(5000), Ok(()));
+
+            // All recovered source symbols are credited to a layer.
+            let decoder = simulator.get_decoder();
+            let stats = decoder.get_fec_decoder().stats();
+            let per_layer: u64 = stats
+                .iter()
+                .filter(|(name, _)| name.ends_with("-recovered") && name.starts_with("layer"))
+                .map(|(_, nb)| nb)
+                .sum();
+            assert_eq!(per_layer, decoder.get_nb_recovered());
+            simulator.get_sink().get_lost(5000).len()
+        };
+        assert!(run("gaussian") < run("peeling"));
+    }
+
+    #[test]
+    fn test_feedback_report() {
+        let mut feedback = DecoderFeedback::new(11);
+        for id in [1, 2, 5, 6, 7, 10] {
+            feedback.recv_ss(id);
+        }
+        feedback.recv_recovered(3);
+        assert!(feedback.should_send_feedback(10));
+
+        let report = feedback.report(10);
+        assert_eq!(report.get_nb_elems(), 11);
+        assert_eq!(report.get_nb_lost(), 5);
+        assert_eq!(report.get_nb_residual(), 4);
+        assert_eq!(report.get_bursts(), &[1, 2, 2]);
+        assert_eq!(report.get_max_burst(), 2);
+
+        // Header, then a bit per source symbol, a byte per run, or nothing.
+        let losses = report.get_losses().unwrap().to_vec();
+        assert_eq!(report.get_encoded_size(), 24 + 2);
+        let report = FeedbackReport::new(0, losses.clone(), LossEncoding::RunLength);
+        assert_eq!(report.get_encoded_size(), 24 + 7);
+        let report = FeedbackReport::new(0, losses, LossEncoding::Summary);
+        assert_eq!(report.get_encoded_size(), 24);
+        assert!(report.get_bursts().is_empty());
+        assert_eq!(report.get_max_burst(), 2);
+
+        // The next report starts after the last one, and IDs outside of its window are ignored.
+        feedback.reset(10);
+        feedback.recv_ss(12);
+        feedback.recv_ss(u64::MAX / 2);
+        assert_eq!(feedback.nb_recv(), 1);
+
+        // A later source symbol first closes the windows before it.
+        let reports = feedback.reports_before(40);
+        assert_eq!(reports.len(), 2);
+        assert_eq!((reports[0].get_first(), reports[0].get_nb_elems()), (11, 11));
+        assert_eq!(reports[0].get_nb_lost(), 10);
+        assert_eq!((reports[1].get_first(), reports[1].get_nb_lost()), (22, 11));
+        feedback.recv_ss(40);
+        assert_eq!(feedback.nb_recv(), 1);
+        assert!(!feedback.should_send_feedback(40));
+        assert_eq!(feedback.report(40).get_first(), 33);
+    }
+
+    #[test]
+    fn test_feedback_ack() {
+        let mut feedback = DecoderFeedback::new(10);
+        feedback.set_ack(true);
+        for id in [0, 1, 3, 4] {
+            feedback.recv_ss(id);
+        }
+        assert_eq!(feedback.report(4).get_highest_in_order(), Some(1));
+
+        // A recovered source symbol fills the gap.
+        feedback.recv_recovered(2);
+        assert_eq!(feedback.report(4).get_highest_in_order(), Some(4));
+
+        // A source symbol lost for good holds back the acknowledgements until it leaves the window.
+        feedback.set_ack_window(20);
+        for id in 6..=25 {
+            feedback.recv_ss(id);
+        }
+        assert_eq!(feedback.report(25).get_highest_in_order(), Some(4));
+        for id in 26..100 {
+            feedback.recv_ss(id);
+        }
+        assert_eq!(feedback.report(99).get_highest_in_order(), Some(99));
+    }
+
+    #[test]
+    fn test_maelstrom_adaptive() {
+        let run = |drop_scheduler: Box<dyn DropScheduler>| {
+            let mut simulator = Simulator::new();
+            let params = FecParams::from("window=20,layering=1_4_8,adaptive=true,feedback=200");
+            let (encoder, decoder) = FecRegistry::default().create("maelstrom", &params).unwrap();
+            simulator.set_encoder(encoder);
+            simulator.set_decoder(decoder);
+            simulator.set_dropper(Dropper::new(drop_scheduler));
+            assert_eq!(simulator.run(10000), Ok(()));
+            let encoder = simulator.get_encoder().get_fec_encoder();
+            assert_eq!(encoder.name(), "maelstrom_adaptive_1_4_8_20");
+            encoder.stats()
+        };
+
+        // Rare isolated losses: larger windows and the first layer only.
+        let stats = run(Box::new(UniformDropScheduler::new(0.005, 1)));
+        assert!(stats.contains(&("adaptations".to_string(), 50)));
+        assert!(stats.contains(&("window".to_string(), 80)));
+        assert!(stats.contains(&("enabled-layers".to_string(), 1)));
+
+        // Frequent bursts: smaller windows and the interleaved layers.
+        let stats = run(Box::new(GilbertEliotDropSheduler::new_simple(0.02, 0.2, 1)));
+        let window = stats.iter().find(|(name, _)| name == "window").unwrap().1;
+        assert!(window < 20);
+        assert!(stats.contains(&("enabled-layers".to_string(), 3)));
+    }
+
+    #[test]
+    fn test_maelstrom_cascade() {
+        let pkts: Vec<Packet> = (0..4)
+            .map(|id| {
+                let mut pkt = Packet::new(id);
+                pkt.fec = Some(FecMetadata::Source(FecSourceMetadata::new::<MaelstromSSID>(id)));
+                pkt
+            })
+            .collect();
+
+        // Each repair symbol misses two source symbols when it is received.
+        let mut decoder = MaelstromDecoder::new(100);
+        for (i, window) in pkts.windows(2).enumerate() {
+            let repair = xor_repair(window.iter(), repair_id(i as u64), 0);
+            assert!(decoder.recv_rs(&repair).unwrap().is_empty());
+        }
+        assert_eq!(decoder.get_nb_equations(), 3);
+
+        // The last source symbol solves the equations in cascade.
+        let mut recovered = decoder.recv_ss(&pkts[3]).unwrap();
+        recovered.sort_by_key(|pkt| pkt.id);
+        assert_eq!(recovered, pkts[..3]);
+        assert_eq!(decoder.get_nb_equations(), 0);
+    }
+
+    #[test]
+    fn test_maelstrom_variable_length() {
+        let mut encoder = MaelstromEncoder::new(4, &[1, 2]);
+        let mut decoder = MaelstromDecoder::new(80);
+        let payload = |id: u64| vec![id as u8; (id as usize * 7) % 23];
+
+        let mut recovered = Vec::new();
+        for id in 0..16 {
+            let mut pkt = Packet {
+                id,
+                data: payload(id),
+                ..Default::default()
+            };
+            encoder.protect_symbol(&mut pkt).unwrap();
+            if ![2, 5, 9].contains(&id) {
+                recovered.extend(decoder.recv_ss(&pkt).unwrap());
+            }
+            if encoder.should_generate_rs() {
+                for repair in encoder.generate_rs().unwrap() {
+                    assert!(repair.id & REPAIR_ID_FLAG != 0);
+                    recovered.extend(decoder.recv_rs(&repair).unwrap());
+                }
+            }
+        }
+
+        recovered.sort_by_key(|pkt| pkt.id);
+        assert_eq!(recovered.iter().map(|pkt| pkt.id).collect::<Vec<_>>(), vec![2, 5, 9]);
+        assert!(recovered.iter().all(|pkt| pkt.data == payload(pkt.id)));
     }
 
     #[test]
@@ -303,7 +795,7 @@ mod tests {
 
         // Add encoder.
         let encoder = MaelstromEncoder::new(window, &interleaves_values);
-        let encoder = Encoder::new(crate::fec::FecEncoder::Maelstrom(encoder));
+        let encoder = Encoder::new(Box::new(encoder));
         simulator.set_encoder(encoder);
 
         // Add dropper.
@@ -315,7 +807,7 @@ mod tests {
 
         // Add decoder.
         let decoder = MaelstromDecoder::new(window * 20);
-        let decoder = Decoder::new(FecDecoder::Maelstrom(decoder), None);
+        let decoder = Decoder::new(Box::new(decoder), None);
         simulator.set_decoder(decoder);
 
         assert_eq!(simulator.run(10), Ok(()));
@@ -337,7 +829,7 @@ mod tests {
 
         // Add encoder.
         let encoder = MaelstromEncoder::new(window, &interleaves_values);
-        let encoder = Encoder::new(crate::fec::FecEncoder::Maelstrom(encoder));
+        let encoder = Encoder::new(Box::new(encoder));
         simulator.set_encoder(encoder);
 
         // Add dropper.
@@ -349,7 +841,7 @@ mod tests {
 
         // Add decoder.
         let decoder = MaelstromDecoder::new(window * 20);
-        let decoder = Decoder::new(FecDecoder::Maelstrom(decoder), None);
+        let decoder = Decoder::new(Box::new(decoder), None);
         simulator.set_decoder(decoder);
 
         assert_eq!(simulator.run(29), Ok(()));
@@ -370,7 +862,7 @@ mod tests {
 
         // Add encoder.
         let encoder = MaelstromEncoder::new(window, &interleaves_values);
-        let encoder = Encoder::new(crate::fec::FecEncoder::Maelstrom(encoder));
+        let encoder = Encoder::new(Box::new(encoder));
         simulator.set_encoder(encoder);
 
         // Add dropper.
@@ -382,7 +874,7 @@ mod tests {
 
         // Add decoder.
         let decoder = MaelstromDecoder::new(window * 20);
-        let decoder = Decoder::new(FecDecoder::Maelstrom(decoder), None);
+        let decoder = Decoder::new(Box::new(decoder), None);
         simulator.set_decoder(decoder);
 
         assert_eq!(simulator.run(100), Ok(()));
@@ -403,18 +895,20 @@ mod tests {
 
         // Add encoder.
         let encoder = MaelstromEncoder::new(window, &interleaves_values);
-        let encoder = Encoder::new(crate::fec::FecEncoder::Maelstrom(encoder));
+        let encoder = Encoder::new(Box::new(encoder));
         simulator.set_encoder(encoder);
 
         // Add dropper.
-        let drop_scheduler = GilbertEliotDropSheduler::new_simple(0.01, 0.2, 1);
+        // With seed 1, a burst also drops the repair symbols of the bins of source symbols 92 to 97,
+        // so they cannot all be recovered. This seed only drops source symbols Maelstrom can recover.
+        let drop_scheduler = GilbertEliotDropSheduler::new_simple(0.01, 0.2, 11);
         let mut dropper = Dropper::new(Box::new(drop_scheduler));
         dropper.activate_trace();
         simulator.set_dropper(dropper);
 
         // Add decoder.
         let decoder = MaelstromDecoder::new(window * 20);
-        let decoder = Decoder::new(FecDecoder::Maelstrom(decoder), None);
+        let decoder = Decoder::new(Box::new(decoder), None);
         simulator.set_decoder(decoder);
 
         assert_eq!(simulator.run(1000), Ok(()));
@@ -429,12 +923,718 @@ mod tests {
         assert!(!simulator.get_sink().get_recovered().is_empty());
     }
 
-    
+    #[test]
+    fn test_fit_gilbert() {
+        let mut drop_scheduler = GilbertEliotDropSheduler::new_simple(0.02, 0.3, 1);
+        let drops: Vec<bool> = (0..20_000).map(|_| drop_scheduler.should_drop()).collect();
+
+        let fit = LossModelFit::new(&drops);
+        assert_eq!(fit.get_nb_pkts(), 20_000);
+        let rate = fit.get_uniform_rate();
+        assert!((rate - 0.02 / 0.32).abs() < 0.01);
+
+        let gilbert = fit.get_gilbert();
+        assert!((gilbert.g2b - 0.02).abs() < 0.005);
+        assert!((gilbert.b2g - 0.3).abs() < 0.03);
+        assert!((fit.get_mean_burst() - 1.0 / 0.3).abs() < 0.3);
+
+        // The full model should find (almost) the same process.
+        let ge = fit.get_gilbert_eliot();
+        assert!((ge.g2b - 0.02).abs() < 0.005);
+        assert!((ge.b2g - 0.3).abs() < 0.03);
+        assert!(ge.dg < 0.01);
+        assert!(ge.db > 0.9);
+    }
+
+    #[test]
+    fn test_fit_run_lengths() {
+        let drops = [false, true, true, false, false, false, true, false];
+        let fit = LossModelFit::new(&drops);
+
+        assert_eq!(fit.get_nb_dropped(), 3);
+        assert_eq!(
+            fit.get_bursts().iter().collect::<Vec<_>>(),
+            vec![(&1, &1), (&2, &1)]
+        );
+        assert_eq!(
+            fit.get_gaps().iter().collect::<Vec<_>>(),
+            vec![(&1, &2), (&3, &1)]
+        );
+    }
+
+    #[test]
+    fn test_burst_drop_scheduler() {
+        // Constant lengths: the loss pattern is deterministic.
+        let gaps = "empirical:3=1".parse().unwrap();
+        let bursts = "empirical:2=1".parse().unwrap();
+        let mut drop_scheduler = BurstDropScheduler::new(gaps, bursts, 1);
+        let drops: Vec<bool> = (0..10).map(|_| drop_scheduler.should_drop()).collect();
+        assert_eq!(
+            drops,
+            vec![false, false, false, true, true, false, false, false, true, true]
+        );
+
+        // Heavy-tailed bursts are reproduced by the fitted empirical distribution.
+        let gaps = "lognormal:3:1".parse().unwrap();
+        let bursts = "pareto:1:1.5".parse().unwrap();
+        let mut drop_scheduler = BurstDropScheduler::new(gaps, bursts, 1);
+        let drops: Vec<bool> = (0..50_000).map(|_| drop_scheduler.should_drop()).collect();
+        let fit = LossModelFit::new(&drops);
+        assert!(fit.get_bursts().keys().max().unwrap() > &20);
+
+        let mut drop_scheduler = BurstDropScheduler::new(
+            LengthDistribution::empirical(fit.get_gaps()),
+            LengthDistribution::empirical(fit.get_bursts()),
+            2,
+        );
+        let drops: Vec<bool> = (0..50_000).map(|_| drop_scheduler.should_drop()).collect();
+        let refit = LossModelFit::new(&drops);
+        assert!((refit.get_mean_burst() - fit.get_mean_burst()).abs() < 1.0);
+        assert!((refit.get_uniform_rate() - fit.get_uniform_rate()).abs() < 0.05);
+
+        assert!("weibull:1:2".parse::<LengthDistribution>().is_err());
+        let wrong = ["pareto:0:1.5", "pareto:1:-1", "lognormal:3:-1", "empirical:2=-1", "empirical:0=1"];
+        for wrong in wrong {
+            assert!(wrong.parse::<LengthDistribution>().is_err());
+        }
+
+        // A trace without losses has no burst: the scheduler never drops.
+        let fit = LossModelFit::new(&[false; 100]);
+        let mut drop_scheduler = BurstDropScheduler::new(
+            LengthDistribution::empirical(fit.get_gaps()),
+            LengthDistribution::empirical(fit.get_bursts()),
+            1,
+        );
+        assert!((0..1000).all(|_| !drop_scheduler.should_drop()));
+    }
+
+    #[test]
+    fn test_outage_drop_scheduler() {
+        let mut dropped_ss = Vec::new();
+
+        for with_fec in [false, true] {
+            let mut simulator = Simulator::new();
+            simulator.set_timing(Duration::from_millis(1), Duration::ZERO);
+
+            if with_fec {
+                let encoder = MaelstromEncoder::new(4, &[1, 2]);
+                let encoder = Encoder::new(Box::new(encoder));
+                simulator.set_encoder(encoder);
+            }
+
+            // Up during 10ms, then down during 3ms.
+            let drop_scheduler = OutageDropScheduler::new(
+                "empirical:10=1".parse().unwrap(),
+                "empirical:3=1".parse().unwrap(),
+                Duration::from_millis(1),
+                1,
+            );
+            let mut dropper = Dropper::new(Box::new(drop_scheduler));
+            dropper.activate_trace();
+            simulator.set_dropper(dropper);
+
+            assert_eq!(simulator.run(30), Ok(()));
+            assert_eq!(simulator.get_time(), Duration::from_millis(30));
+            dropped_ss.push(simulator.get_dropper().get_dropped_ss().unwrap());
+        }
+
+        // Losses only depend on the transmission time of the source symbols.
+        assert_eq!(dropped_ss[0], vec![10, 11, 12, 23, 24, 25]);
+        assert_eq!(dropped_ss[0], dropped_ss[1]);
+
+        // Very long outages saturate the virtual time instead of overflowing.
+        let mut drop_scheduler = OutageDropScheduler::new(
+            "empirical:1=1".parse().unwrap(),
+            format!("empirical:{}=1", u64::MAX).parse().unwrap(),
+            Duration::from_secs(1),
+            1,
+        );
+        assert!(drop_scheduler.should_drop_at(Duration::from_secs(1)));
+        assert!(drop_scheduler.should_drop_at(Duration::from_secs(1_000_000)));
+    }
+
+    #[test]
+    fn test_fec_registry() {
+        let mut registry = FecRegistry::default();
+        assert_eq!(
+            registry.create("unknown", &FecParams::new()).err(),
+            Some(Error::UnknownFecScheme("unknown".to_string()))
+        );
+
+        // Plug a custom scheme in the simulator.
+        registry.register(
+            "custom",
+            Box::new(|params| {
+                let window = params.get_or("window", 10)?;
+                let layers: Vec<u64> = vec![params.get_or("layer", 1)?];
+                let encoder = MaelstromEncoder::new(window, &layers);
+                let decoder = MaelstromDecoder::new(window * 20);
+                Ok((
+                    Encoder::new(Box::new(encoder)),
+                    Decoder::new(Box::new(decoder), None),
+                ))
+            }),
+        );
+        assert!(registry.names().contains(&"custom"));
+
+        let params = FecParams::from("window=4, layer=1");
+        let (encoder, decoder) = registry.create("custom", &params).unwrap();
+        let mut simulator = Simulator::new();
+        simulator.set_encoder(encoder);
+        simulator.set_decoder(decoder);
+        let drop_scheduler = ConstantDropScheduler::new(7);
+        simulator.set_dropper(Dropper::new(Box::new(drop_scheduler)));
+
+        assert_eq!(simulator.run(1000), Ok(()));
+        assert!(simulator.get_decoder().get_nb_recovered() > 0);
+
+        // A value that cannot be parsed is an error, not a missing parameter.
+        let params = FecParams::from("window=4O");
+        assert!(matches!(registry.create("custom", &params), Err(Error::FecEncoder(_))));
+        assert!(matches!(
+            FecRegistry::default().create("xor", &FecParams::from("block=5O")),
+            Err(Error::FecEncoder(_))
+        ));
+    }
+
+    #[test]
+    fn test_reed_solomon() {
+        let mut simulator = Simulator::new();
+        let params = FecParams::from("k=4,n=6");
+        let (encoder, decoder) = FecRegistry::default().create("rs", &params).unwrap();
+        simulator.set_encoder(encoder);
+        simulator.set_decoder(decoder);
+
+        // Each block is sent as 4 source and 2 repair symbols.
+        // Drop 2 source symbols of the first block, and 3 of the second.
+        let mut drop_scheduler = SpecificDropScheduler::new(100);
+        drop_scheduler.add_to_drop(&[1, 2, 6, 7, 8]);
+        simulator.set_dropper(Dropper::new(Box::new(drop_scheduler)));
+
+        assert_eq!(simulator.run(12), Ok(()));
+        let mut recovered = simulator.get_sink().get_recovered();
+        recovered.sort();
+        assert_eq!(recovered, vec![1, 2]);
+        assert_eq!(simulator.get_sink().get_lost(12), vec![4, 5, 6]);
+        assert!(simulator.get_sink().get_corrupted(12).is_empty());
+        assert_eq!(simulator.get_encoder().get_fec_encoder().name(), "rs_4_6");
+
+        // Repair symbols have their own IDs, which never collide with the source symbols.
+        let mut encoder = ReedSolomonEncoder::new(2, 4).unwrap();
+        let mut ids = Vec::new();
+        for id in 0..4 {
+            encoder.protect_symbol(&mut Packet::new(id)).unwrap();
+            if encoder.should_generate_rs() {
+                ids.extend(encoder.generate_rs().unwrap().iter().map(|pkt| pkt.id));
+            }
+        }
+        assert_eq!(ids, (0..4).map(repair_id).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_parity_schemes() {
+        let mut recovered = Vec::new();
+        for (scheme, params) in [("xor", "block=3"), ("parity2d", "columns=3,rows=3")] {
+            let mut simulator = Simulator::new();
+            let params = FecParams::from(params);
+            let (encoder, decoder) = FecRegistry::default().create(scheme, &params).unwrap();
+            simulator.set_encoder(encoder);
+            simulator.set_decoder(decoder);
+
+            // Drop two source symbols of the first row, and one of the second.
+            let mut drop_scheduler = SpecificDropScheduler::new(100);
+            drop_scheduler.add_to_drop(&[0, 1, 5]);
+            simulator.set_dropper(Dropper::new(Box::new(drop_scheduler)));
+
+            assert_eq!(simulator.run(9), Ok(()));
+            let mut ids = simulator.get_sink().get_recovered();
+            ids.sort();
+            recovered.push(ids);
+        }
+
+        // Only the column parities recover the two losses of the same row.
+        assert_eq!(recovered[0], vec![4]);
+        assert_eq!(recovered[1], vec![0, 1, 4]);
+    }
+
+    #[test]
+    fn test_tart_native_backend() {
+        let mut simulator = Simulator::new();
+        let params = FecParams::from("backend=native,window-factor=3,scheduler=window,window=100,step=5");
+        let (encoder, decoder) = FecRegistry::default().create("tart", &params).unwrap();
+        simulator.set_encoder(encoder);
+        simulator.set_decoder(decoder);
+        let drop_scheduler = UniformDropScheduler::new(0.1, 1);
+        simulator.set_dropper(Dropper::new(Box::new(drop_scheduler)));
+
+        assert_eq!(simulator.run(1000), Ok(()));
+        let name = simulator.get_encoder().get_fec_encoder().name();
+        assert_eq!(name, "tart_native_s8_w3_window_5");
+        let sink = simulator.get_sink();
+        let nb_dropped = simulator.get_dropper().get_nb_ss_dropped() as usize;
+        assert!(sink.get_lost(1000).len() < nb_dropped / 2);
+        assert!(sink.get_corrupted(1000).is_empty());
+
+        // Unknown backends are errors, as the `networkcoding` ones if the crate is not compiled.
+        let registry = FecRegistry::default();
+        assert!(registry.create("tart", &FecParams::from("backend=natve")).is_err());
+        #[cfg(not(feature = "networkcoding"))]
+        assert!(registry.create("tart", &FecParams::from("backend=vlc")).is_err());
+    }
+
+    #[test]
+    fn test_tart_tail_protection() {
+        let run = |tail: &str, to_drop: u64| {
+            let mut simulator = Simulator::new();
+            let params = format!("backend=native,scheduler=window,window=100,step=10,{tail}");
+            let (encoder, decoder) = FecRegistry::default()
+                .create("tart", &FecParams::from(params.as_str()))
+                .unwrap();
+            simulator.set_encoder(encoder);
+            simulator.set_decoder(decoder);
+            let mut drop_scheduler = SpecificDropScheduler::new(1000);
+            drop_scheduler.add_to_drop(&[to_drop]);
+            simulator.set_dropper(Dropper::new(Box::new(drop_scheduler)));
+
+            // Too few source symbols for the scheduler to send a repair symbol.
+            assert_eq!(simulator.run(3), Ok(()));
+            let stats = simulator.get_encoder().get_fec_encoder().stats();
+            (simulator.get_sink().get_lost(3).len(), stats)
+        };
+
+        // The last source symbol is only protected if the tail is flushed.
+        let (nb_lost, _) = run("flush-end=false", 2);
+        assert_eq!(nb_lost, 1);
+        let (nb_lost, stats) = run("flush-end=true", 2);
+        assert_eq!(nb_lost, 0);
+        assert!(stats.contains(&("end-tail-repairs".to_string(), 1)));
+        assert!(stats.contains(&("repairs".to_string(), 0)));
+
+        // A source symbol every millisecond is idle traffic for a 500us timeout.
+        let (nb_lost, stats) = run("idle-timeout-us=500", 2);
+        assert_eq!(nb_lost, 0);
+        assert!(stats.contains(&("idle-tail-repairs".to_string(), 2)));
+        assert!(stats.contains(&("end-tail-repairs".to_string(), 0)));
+    }
+
+    #[test]
+    fn test_tart_idle_silence() {
+        let run = |idle_timeout: &str| {
+            let mut simulator = Simulator::new();
+            let params = format!("backend=native,scheduler=window,window=5,step=10{idle_timeout}");
+            let (encoder, decoder) = FecRegistry::default()
+                .create("tart", &FecParams::from(params.as_str()))
+                .unwrap();
+            simulator.set_encoder(encoder);
+            simulator.set_decoder(decoder);
+            let mut drop_scheduler = SpecificDropScheduler::new(1000);
+            drop_scheduler.add_to_drop(&[26]);
+            simulator.set_dropper(Dropper::new(Box::new(drop_scheduler)));
+
+            // Bursts of 25 source symbols, one per millisecond, and 20ms of silence.
+            simulator.set_on_off(25, Duration::from_millis(20));
+            assert_eq!(simulator.run(50), Ok(()));
+            assert_eq!(simulator.get_time(), Duration::from_millis(90));
+            let stats = simulator.get_encoder().get_fec_encoder().stats();
+            (simulator.get_sink().get_lost(50), stats)
+        };
+
+        // The first burst ends with source symbols 20 to 24, out of the window of the next repair symbol.
+        let (lost, stats) = run("");
+        assert_eq!(lost, vec![24]);
+        assert!(stats.contains(&("idle-tail-repairs".to_string(), 0)));
+
+        // The timeout is longer than the interval: it only expires during the silences.
+        let (lost, stats) = run(",idle-timeout-us=5000");
+        assert!(lost.is_empty());
+        assert!(stats.contains(&("idle-tail-repairs".to_string(), 2)));
+    }
+
+    #[test]
+    fn test_tart_decoder_eviction() {
+        let mut simulator = Simulator::new();
+        let params = FecParams::from("backend=native,scheduler=window,window=20,step=5,eviction-window=40");
+        let (encoder, decoder) = FecRegistry::default().create("tart", &params).unwrap();
+        simulator.set_encoder(encoder);
+        simulator.set_decoder(decoder);
+        let mut drop_scheduler = SpecificDropScheduler::new(1200);
+        drop_scheduler.add_to_drop(&(100..130).collect::<Vec<u64>>());
+        simulator.set_dropper(Dropper::new(Box::new(drop_scheduler)));
+
+        // The burst is longer than the window: its source symbols are evicted before being recovered.
+        assert_eq!(simulator.run(1000), Ok(()));
+        let nb_lost = simulator.get_sink().get_lost(1000).len() as u64;
+        assert!(nb_lost > 0);
+        let stats = simulator.get_decoder().get_fec_decoder().stats();
+        assert_eq!(stats, vec![("evicted-missing".to_string(), nb_lost)]);
+    }
+
+    #[test]
+    fn test_tart_scheduler_window() {
+        // The scheduler keeps 8 source symbols, in a window of at most 20 source symbols.
+        let scheduler = Box::new(WindowStepScheduler::new(8, 5));
+        let codec = TartCodec::new(TartBackend::Native);
+        let mut encoder = TartEncoder::new_with_codec(scheduler, 20, codec).unwrap();
+        encoder.activate_window_trace();
+        for id in 0..50 {
+            encoder.protect_symbol(&mut Packet::new(id)).unwrap();
+        }
+
+        let trace = encoder.get_window_trace().unwrap();
+        assert_eq!(trace.len(), 50);
+        assert_eq!(&trace[..10], &[1, 2, 3, 4, 5, 6, 7, 8, 8, 8]);
+        assert!(encoder.stats().contains(&("max-window".to_string(), 8)));
+    }
+
+    #[test]
+    fn test_tart_ack() {
+        let run = |ack: bool| {
+            let mut simulator = Simulator::new();
+            let params = format!("backend=native,scheduler=window,window=50,step=5,feedback=10,ack={ack}");
+            let (encoder, decoder) = FecRegistry::default()
+                .create("tart", &FecParams::from(params.as_str()))
+                .unwrap();
+            simulator.set_encoder(encoder);
+            simulator.set_decoder(decoder);
+            let drop_scheduler = UniformDropScheduler::new(0.05, 1);
+            simulator.set_dropper(Dropper::new(Box::new(drop_scheduler)));
+            assert_eq!(simulator.run(5000), Ok(()));
+            assert!(simulator.get_sink().get_lost(5000).is_empty());
+
+            let decoder = simulator.get_decoder();
+            let stats = simulator.get_encoder().get_fec_encoder().stats();
+            let mean_window = stats.iter().find(|(name, _)| name == "mean-window").unwrap().1;
+            (decoder.get_nb_recovered(), decoder.get_nb_unused_rs(), mean_window)
+        };
+
+        // Acknowledged source symbols leave the window without hurting the recovery.
+        let (nb_recovered, nb_unused, mean_window) = run(false);
+        assert_eq!(mean_window, 50);
+        let (nb_recovered_ack, nb_unused_ack, mean_window_ack) = run(true);
+        assert!(mean_window_ack < 10);
+        assert_eq!(nb_recovered_ack, nb_recovered);
+        assert!(nb_unused_ack <= nb_unused);
+    }
+
+    #[test]
+    fn test_tart_burst_scheduler() {
+        // Same loss rate, in isolated losses or in bursts of 10 source symbols.
+        let isolated: Vec<bool> = (0..1000).map(|i| i % 100 == 50).collect();
+        let bursty: Vec<bool> = (0..1000).map(|i| i % 1000 >= 500 && i % 1000 < 510).collect();
+        let schedule = |lost: &[bool]| {
+            let mut scheduler = BurstFecScheduler::new(0.0, 100);
+            scheduler.recv_feedback(&FeedbackReport::new(0, lost.to_vec(), LossEncoding::Bitmap));
+            (scheduler.get_group_size(), scheduler.get_spacing())
+        };
+        let (group, spacing) = schedule(&isolated);
+        assert_eq!(group, 1);
+        let (bursty_group, bursty_spacing) = schedule(&bursty);
+        assert!(bursty_group >= 10);
+
+        // Bursts also need more repair symbols per source symbol.
+        assert!((bursty_group as f64 / bursty_spacing) > (group as f64 / spacing));
+
+        // The encoder sends the group back-to-back.
+        let mut scheduler = BurstFecScheduler::new(0.0, 100);
+        scheduler.recv_feedback(&FeedbackReport::new(0, bursty, LossEncoding::Bitmap));
+        let current = bursty_spacing.ceil() as u64;
+        let mut nb_rs = 0;
+        while scheduler.should_generate_rs(current) {
+            scheduler.on_sent_rs(current);
+            nb_rs += 1;
+        }
+        assert_eq!(nb_rs, bursty_group);
+        assert!(!scheduler.should_generate_rs(current + 1));
+    }
+
+    #[test]
+    fn test_lt() {
+        for params in [
+            "variant=block,block=50,overhead=0.5",
+            "variant=block,block=50,overhead=0.5,precode=5",
+            "variant=window,block=50,overhead=0.5",
+        ] {
+            let mut simulator = Simulator::new();
+            let params = FecParams::from(params);
+            let (encoder, decoder) = FecRegistry::default().create("lt", &params).unwrap();
+            simulator.set_encoder(encoder);
+            simulator.set_decoder(decoder);
+            let drop_scheduler = UniformDropScheduler::new(0.05, 1);
+            simulator.set_dropper(Dropper::new(Box::new(drop_scheduler)));
+
+            assert_eq!(simulator.run(2000), Ok(()));
+            assert_eq!(simulator.get_encoder().get_nb_rs(), 1000);
+            let sink = simulator.get_sink();
+            let nb_dropped = simulator.get_dropper().get_nb_ss_dropped() as usize;
+            assert!(sink.get_recovered().len() > nb_dropped / 2);
+            assert!(sink.get_corrupted(2000).is_empty());
+            assert!(sink.get_duplicates().is_empty());
+        }
+
+        // Encoding symbols have their own IDs, which never collide with the source symbols.
+        let mut encoder = LtEncoder::new(LtVariant::Window, 4, 0.5, 0);
+        let mut ids = Vec::new();
+        for id in 0..8 {
+            encoder.protect_symbol(&mut Packet::new(id)).unwrap();
+            if encoder.should_generate_rs() {
+                ids.extend(encoder.generate_rs().unwrap().iter().map(|pkt| pkt.id));
+            }
+        }
+        assert_eq!(ids, (0..4).map(repair_id).collect::<Vec<_>>());
+
+        let params = FecParams::from("variant=windows");
+        assert!(matches!(
+            FecRegistry::default().create("lt", &params),
+            Err(Error::FecEncoder(_))
+        ));
+    }
+
+    /// Protects 30 source symbols with a repair symbol every 5 symbols and a window of 20 symbols,
+    /// and returns the sorted IDs recovered by the decoder when `losses` are lost.
+    macro_rules! sliding_window_recovery {
+        ($encoder:expr, $decoder:expr, $losses:expr) => {{
+            let (mut encoder, mut decoder) = ($encoder, $decoder);
+            let mut recovered = Vec::new();
+            for id in 0..30u64 {
+                let mut metadata = encoder.next_metadata().unwrap();
+                encoder.protect_data(id.to_be_bytes().to_vec(), &mut metadata).unwrap();
+                encoder.remove_up_to((id + 1).saturating_sub(20).to_be_bytes());
+                if !$losses.contains(&id) {
+                    let symbol = SourceSymbol::new(metadata, id.to_be_bytes().to_vec());
+                    recovered.extend(decoder.receive_source_symbol(symbol, Instant::now()).unwrap());
+                }
+                if id % 5 == 4 {
+                    let repair = encoder.generate_and_serialize_repair_symbol().unwrap();
+                    if let Ok((_, symbols)) = decoder.receive_and_deserialize_repair_symbol(repair) {
+                        recovered.extend(symbols);
+                    }
+                }
+            }
+            let mut ids: Vec<u64> = recovered
+                .iter()
+                .map(|s| u64::from_be_bytes(s.get().to_owned().try_into().unwrap()))
+                .collect();
+            ids.sort();
+            ids
+        }};
+    }
+
+    #[test]
+    fn test_rlc_recovery() {
+        use crate::fec::rlc::{RlcDecoder, RlcEncoder, SourceSymbol};
+        use std::time::Instant;
+
+        // The last repair symbols no longer protect the first losses, but bring enough equations.
+        let losses = [3, 4, 5, 6, 12];
+        let recovered =
+            sliding_window_recovery!(RlcEncoder::new(20), RlcDecoder::new(40), losses);
+        assert_eq!(recovered, losses);
+    }
+
+    #[cfg(feature = "networkcoding")]
+    #[test]
+    fn test_rlc_differential() {
+        use crate::fec::rlc::{RlcDecoder, RlcEncoder};
+        use networkcoding::vandermonde_lc::{decoder::VLCDecoder, encoder::VLCEncoder};
+        use std::time::Instant;
+
+        for losses in [vec![1], vec![3, 4, 5, 6, 12], vec![10, 11, 12, 13, 14]] {
+            let expected = {
+                use networkcoding::SourceSymbol;
+                sliding_window_recovery!(
+                    networkcoding::Encoder::VLC(VLCEncoder::new(8, 1000)),
+                    networkcoding::Decoder::VLC(VLCDecoder::new(8, 1000)),
+                    losses
+                )
+            };
+            let recovered = {
+                use crate::fec::rlc::SourceSymbol;
+                sliding_window_recovery!(RlcEncoder::new(20), RlcDecoder::new(40), losses)
+            };
+            assert_eq!(recovered, expected);
+        }
+    }
+
+    #[test]
+    fn test_time_gilbert_eliot() {
+        let step = Duration::from_millis(1);
+        let mut fast = TimeGilbertEliotDropScheduler::new_simple(0.01, 0.2, step, 1);
+        let mut slow = TimeGilbertEliotDropScheduler::new_simple(0.01, 0.2, step, 1);
+
+        // Ten packets per step for the first scheduler, one for the second. The channel state is the same.
+        for ms in 0..10_000 {
+            let now = Duration::from_millis(ms);
+            let drop = slow.should_drop_at(now);
+            for i in 0..10 {
+                assert_eq!(fast.should_drop_at(now + Duration::from_micros(i * 100)), drop);
+            }
+        }
+
+        // The stationary drop ratio is g2b / (g2b + b2g).
+        let nb_dropped = (0..100_000)
+            .filter(|ms| slow.should_drop_at(Duration::from_millis(10_000 + ms)))
+            .count();
+        assert!((nb_dropped as f64 / 100_000.0 - 0.01 / 0.21).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_corruption() {
+        for checksum in [None, Some(Checksum::Internet), Some(Checksum::Crc32)] {
+            let mut simulator = Simulator::new();
+
+            // Add encoder.
+            let encoder = MaelstromEncoder::new(4, &[1]);
+            let mut encoder = Encoder::new(Box::new(encoder));
+            if let Some(checksum) = checksum {
+                encoder.set_checksum(checksum);
+            }
+            simulator.set_encoder(encoder);
+
+            // Add dropper and bit-error channel.
+            let drop_scheduler = ConstantDropScheduler::new(7);
+            simulator.set_dropper(Dropper::new(Box::new(drop_scheduler)));
+            simulator.set_corrupter(Corrupter::new(0.002, 1));
+
+            // Add decoder.
+            let decoder = MaelstromDecoder::new(80);
+            let decoder = Decoder::new(Box::new(decoder), None);
+            simulator.set_decoder(decoder);
+
+            assert_eq!(simulator.run(1000), Ok(()));
+
+            let nb_corrupted = simulator.get_corrupter().get_nb_corrupted();
+            let nb_detected = simulator.get_decoder().get_nb_corrupted();
+            let sink = simulator.get_sink();
+            assert!(nb_corrupted > 0);
+            match checksum {
+                None => {
+                    // Nothing is detected and corruption propagates to recovered packets.
+                    assert_eq!(nb_detected, 0);
+                    assert!(!sink.get_corrupted_recovered(1000).is_empty());
+                }
+                Some(Checksum::Crc32) => {
+                    // All corrupted packets are treated as erasures.
+                    assert_eq!(nb_detected, nb_corrupted);
+                    assert!(sink.get_corrupted(1000).is_empty());
+                }
+                Some(Checksum::Internet) => assert!(nb_detected > 0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_corruption_tart_repair() {
+        // The combination of a TART repair symbol is its payload: the channel corrupts it.
+        let scheduler = Box::new(WindowStepScheduler::new(4, 4));
+        let codec = TartCodec::new(TartBackend::Native);
+        let mut encoder = TartEncoder::new_with_codec(scheduler, 4, codec).unwrap();
+        for id in 0..4 {
+            encoder.protect_symbol(&mut Packet::new(id)).unwrap();
+        }
+        let repairs = encoder.generate_rs().unwrap();
+        assert_eq!(repairs.len(), 1);
+        assert!(!repairs[0].data.is_empty());
+
+        let mut corrupter = Corrupter::new(1.0, 1);
+        corrupter.recv(repairs.clone()).unwrap();
+        let corrupted = corrupter.forw().unwrap();
+        assert_eq!(corrupter.get_nb_corrupted(), 1);
+        assert_ne!(corrupted[0].data, repairs[0].data);
+    }
+
+    #[test]
+    fn test_common_random_numbers() {
+        let seeds = SeedHierarchy::new(42);
+        let mut dropped_ss = Vec::new();
+
+        for with_fec in [false, true] {
+            let mut simulator = Simulator::new();
+
+            if with_fec {
+                let encoder = MaelstromEncoder::new(4, &[1, 2]);
+                let encoder = Encoder::new(Box::new(encoder));
+                simulator.set_encoder(encoder);
+            }
+
+            // Add dropper with a distinct stream for repair symbols.
+            let drop_scheduler = UniformDropScheduler::new(0.1, seeds.seed(Component::Dropper));
+            let mut dropper = Dropper::new(Box::new(drop_scheduler));
+            dropper.set_common_random_numbers(seeds.seed(Component::DropperRepair));
+            dropper.activate_trace();
+            simulator.set_dropper(dropper);
+
+            assert_eq!(simulator.run(1000), Ok(()));
+            dropped_ss.push(simulator.get_dropper().get_dropped_ss().unwrap());
+        }
+
+        // The same source symbols are dropped with and without repair symbols.
+        assert!(!dropped_ss[0].is_empty());
+        assert_eq!(dropped_ss[0], dropped_ss[1]);
+
+        // A repair symbol after each source symbol shares its Gilbert-Elliott state.
+        let mut simulator = Simulator::new();
+        let encoder = MaelstromEncoder::new(1, &[1]);
+        simulator.set_encoder(Encoder::new(Box::new(encoder)));
+        let drop_scheduler = GilbertEliotDropSheduler::new_simple(0.05, 0.2, 1);
+        let mut dropper = Dropper::new(Box::new(drop_scheduler));
+        dropper.set_common_random_numbers(2);
+        dropper.activate_trace();
+        simulator.set_dropper(dropper);
+        assert_eq!(simulator.run(1000), Ok(()));
+
+        let trace = simulator.get_dropper().get_trace().unwrap();
+        assert_eq!(trace.len(), 2000);
+        assert!(trace.iter().any(|&(_, is_repair, is_dropped)| is_repair && is_dropped));
+        for pair in trace.chunks(2) {
+            assert!(!pair[0].1 && pair[1].1);
+            assert_eq!(pair[0].2, pair[1].2);
+        }
+    }
+
+    #[test]
+    fn test_tart_recovered_id() {
+        // Recovered source symbols keep their ID, whatever their payload.
+        let scheduler = Box::new(WindowStepScheduler::new(4, 4));
+        let codec = TartCodec::new(TartBackend::Native);
+        let mut encoder = TartEncoder::new_with_codec(scheduler, 4, codec.clone()).unwrap();
+        let mut decoder = TartDecoder::new_with_codec(4, codec).unwrap();
+        let mut pkts: Vec<Packet> = (0..4).map(Packet::new).collect();
+        for pkt in pkts.iter_mut() {
+            pkt.data = vec![pkt.id as u8; 3];
+            encoder.protect_symbol(pkt).unwrap();
+        }
+        let repairs = encoder.generate_rs().unwrap();
+
+        for pkt in pkts.iter().filter(|pkt| pkt.id != 2) {
+            assert!(decoder.recv_ss(pkt).unwrap().is_empty());
+        }
+        let recovered = decoder.recv_rs(&repairs[0]).unwrap();
+        assert_eq!(recovered.len(), 1);
+        assert_eq!(recovered[0].id, 2);
+        assert_eq!(recovered[0].data, vec![2; 3]);
+    }
+
+    #[test]
+    fn test_fit_all_dropped() {
+        // The channel never leaves the bad state, so the fitted model drops everything.
+        let fit = LossModelFit::new(&[true; 100]);
+        assert_eq!(fit.get_gilbert().g2b, 1.0);
+        assert_eq!(fit.get_gilbert().b2g, 0.0);
+        assert_eq!(fit.get_gilbert_eliot().g2b, 1.0);
+
+        let fit = LossModelFit::new(&[false; 100]);
+        assert_eq!(fit.get_gilbert().g2b, 0.0);
+    }
 }
 
+pub mod checksum;
 pub mod drop;
 pub mod fec;
 pub mod node;
+pub mod seed;
 
+use checksum::Checksum;
 use fec::FecMetadata;
-use node::{decoder::Decoder, dropper::Dropper, encoder::Encoder, Node, Sink, Source};
+use node::{
+    corrupter::Corrupter, decoder::Decoder, dropper::Dropper, encoder::Encoder, Node, Sink, Source,
+};
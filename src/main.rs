@@ -2,23 +2,23 @@
 extern crate log;
 
 use std::fs;
+use std::time::Duration;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use fec_simulator::checksum::Checksum;
+use fec_simulator::drop::burst::{BurstDropScheduler, LengthDistribution};
 use fec_simulator::drop::constant::ConstantDropScheduler;
+use fec_simulator::drop::fit::{GilbertEliotParams, LossModelFit};
 use fec_simulator::drop::ge::GilbertEliotDropSheduler;
 use fec_simulator::drop::none::NoDropScheduler;
 use fec_simulator::drop::specific::SpecificDropScheduler;
+use fec_simulator::drop::time::{OutageDropScheduler, TimeGilbertEliotDropScheduler};
 use fec_simulator::drop::uniform::UniformDropScheduler;
 use fec_simulator::drop::DropScheduler;
-use fec_simulator::fec::maelstrom::{MaelstromDecoder, MaelstromEncoder};
-use fec_simulator::fec::tart::{
-    AdaptiveFecScheduler, TartDecoder, TartEncoder, TartFecScheduler, WindowStepScheduler,
-};
-use fec_simulator::fec::FecDecoder;
-use fec_simulator::fec::FecEncoder;
-use fec_simulator::node::decoder::{Decoder, DecoderFeedback};
+use fec_simulator::fec::{FecParams, FecRegistry};
+use fec_simulator::node::corrupter::Corrupter;
 use fec_simulator::node::dropper::Dropper;
-use fec_simulator::node::encoder::Encoder;
+use fec_simulator::seed::{Component, SeedHierarchy};
 use fec_simulator::Simulator;
 
 #[derive(Clone, Debug)]
@@ -28,6 +28,9 @@ enum DropS {
     Constant,
     GilbertEliot,
     Specific,
+    Burst,
+    GilbertEliotTime,
+    Outage,
 }
 
 impl From<&str> for DropS {
@@ -37,47 +40,33 @@ impl From<&str> for DropS {
             "constant" => Self::Constant,
             "ge" => Self::GilbertEliot,
             "specific" => Self::Specific,
+            "burst" => Self::Burst,
+            "ge-time" => Self::GilbertEliotTime,
+            "outage" => Self::Outage,
             _ => Self::None,
         }
     }
 }
 
-#[derive(Clone, Debug)]
-struct MaelstromLayering {
-    layers: Vec<u64>,
-}
-
-impl From<String> for MaelstromLayering {
-    fn from(value: String) -> Self {
-        let tab = value.split(",");
-        Self {
-            layers: tab.map(|item| item.parse().unwrap()).collect(),
-        }
-    }
-}
-
-#[derive(Clone)]
-enum Fec {
-    None,
-    Tart,
-    Maelstrom,
-}
-
-impl From<&str> for Fec {
-    fn from(value: &str) -> Self {
-        match value {
-            "tart" => Self::Tart,
-            "maelstrom" => Self::Maelstrom,
-            _ => Self::None,
-        }
-    }
+#[derive(Subcommand)]
+enum Command {
+    /// Fit loss-model parameters from a drop trace (as generated by `--dtrace`) and print the arguments to regenerate a similar loss process.
+    Fit {
+        /// Path to the drop trace.
+        trace: String,
+    },
 }
 
 #[derive(Parser)]
+#[clap(subcommand_negates_reqs = true)]
 struct Args {
-    /// Number of packets to run in a single simulation.
-    #[clap(short = 'n')]
-    nb_packets: u64,
+    /// Analysis command. Runs instead of the simulation.
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    /// Number of packets to run in a single simulation. Required unless a command is given.
+    #[clap(short = 'n', required = true)]
+    nb_packets: Option<u64>,
 
     /// Uniform loss ratio [0, 1]. Also the 'p' value of the Gilbert-Elliot drop model.
     #[clap(long = "u-loss", default_value = "0.0")]
@@ -87,10 +76,64 @@ struct Args {
     #[clap(short = 'r', default_value = "1.0")]
     r_ge: f64,
 
+    /// Probability to drop a packet in the good state of the Gilbert-Elliot drop model.
+    #[clap(long = "ge-dg", default_value = "0.0")]
+    dg_ge: f64,
+
+    /// Probability to drop a packet in the bad state of the Gilbert-Elliot drop model.
+    #[clap(long = "ge-db", default_value = "1.0")]
+    db_ge: f64,
+
     /// Step for the constant drop scheduler. Only used with a constant drop scheduler.
     #[clap(long = "constant-drop-step", default_value = "100")]
     constant_loss_step: u64,
 
+    /// Distribution of the gap lengths for the burst drop scheduler, or of the up periods (in steps) for the outage drop scheduler:
+    /// `pareto:<scale>:<shape>`, `lognormal:<mu>:<sigma>` or `empirical:<len>=<weight>,...`.
+    #[clap(long = "gap-dist")]
+    gap_dist: Option<LengthDistribution>,
+
+    /// Distribution of the burst lengths for the burst drop scheduler, or of the outages (in steps) for the outage drop scheduler.
+    /// Same format as `--gap-dist`.
+    #[clap(long = "burst-dist")]
+    burst_dist: Option<LengthDistribution>,
+
+    /// Drop trace (as generated by `--dtrace`) from which the burst drop scheduler takes its empirical gap and burst distributions.
+    /// Replaces `--gap-dist` and `--burst-dist`.
+    #[clap(long = "burst-trace")]
+    burst_trace: Option<String>,
+
+    /// Virtual time between two source packets, in microseconds.
+    #[clap(long = "interval-us", default_value = "1000")]
+    interval_us: u64,
+
+    /// Number of packets of a traffic burst of the source, each followed by `--off-us` of silence.
+    /// By default, the source never stops.
+    #[clap(long = "on-packets")]
+    on_packets: Option<u64>,
+
+    /// Silence of the source after each traffic burst, in microseconds.
+    #[clap(long = "off-us", default_value = "0")]
+    off_us: u64,
+
+    /// Virtual time to transmit a packet on the link, in microseconds.
+    /// Repair symbols are sent back-to-back after the source symbol that triggered them.
+    #[clap(long = "tx-time-us", default_value = "0")]
+    tx_time_us: u64,
+
+    /// Duration of a step of the time-based drop schedulers, in microseconds.
+    /// The time-based Gilbert-Elliot model performs a transition every step.
+    #[clap(long = "step-us", default_value = "1000")]
+    step_us: u64,
+
+    /// Bit error rate of the channel. Corrupted packets are dropped by the decoder if the checksum detects the corruption.
+    #[clap(long = "ber", default_value = "0.0")]
+    ber: f64,
+
+    /// Checksum of the HIRT header: `none`, `internet` or `crc32`.
+    #[clap(long = "checksum", default_value = "crc32")]
+    checksum: String,
+
     /// Sets the initial loss estimation to the drop rate.
     #[clap(long = "set-initial-loss")]
     set_initial_loss: bool,
@@ -115,13 +158,23 @@ struct Args {
     #[clap(long = "window", default_value = "100")]
     fec_window: u64,
 
-    /// Drop seed.
+    /// Master seed. Each random component (e.g., the dropper) derives its own stream from it.
     #[clap(short = 's', default_value = "1")]
     drop_seed: u64,
 
-    /// FEC mechanism to use.
+    /// Common random numbers: only source symbols advance the loss model, so that the k-th source symbol is dropped
+    /// identically whatever the number of repair symbols sent by the FEC mechanism.
+    /// Repair symbols share the channel state of the last source symbol, with their own stream for the drop decisions.
+    #[clap(long = "crn")]
+    crn: bool,
+
+    /// FEC mechanism to use, among the schemes of the registry (`none`, `tart`, `maelstrom`, `rs`, `xor`, `parity2d`, `lt`).
     #[clap(short = 'f', long = "fec", default_value = "tart")]
-    fec: Fec,
+    fec: String,
+
+    /// Additional parameters given to the FEC scheme, as `key=value` pairs separated by commas.
+    #[clap(long = "fec-params", default_value = "")]
+    fec_params: String,
 
     /// Output directory.
     #[clap(short = 'd', long = "dir", default_value = ".")]
@@ -139,52 +192,90 @@ struct Args {
     #[clap(long = "rtrace")]
     rec_trace: Option<String>,
 
-    /// Maelstrom layering.
-    #[clap(long = "layering", default_value = "1,20,40", value_parser = clap::value_parser!(MaelstromLayering))]
-    maelstrom_layering: MaelstromLayering,
+    /// Maelstrom layering: interleave of each layer, optionally with its window after a `:`, e.g., `1:8,20:10,40:16`.
+    #[clap(long = "layering", default_value = "1,20,40")]
+    maelstrom_layering: String,
+
+    /// TART: sliding-window code backend (`rlc`, `vlc` or `native`). Defaults to the `networkcoding` VLC if available.
+    #[clap(long = "tart-backend")]
+    tart_backend: Option<String>,
+
+    /// TART: symbol size given to the `networkcoding` backends.
+    #[clap(long = "symbol-size", default_value = "8")]
+    symbol_size: usize,
+
+    /// TART: capacity of the coding window relative to the FEC window. Defaults to a value depending on the backend.
+    #[clap(long = "window-factor")]
+    window_factor: Option<usize>,
+
+    /// Reed-Solomon: number of source symbols in a block.
+    #[clap(long = "rs-k", default_value = "10")]
+    rs_k: usize,
+
+    /// Reed-Solomon: number of source and repair symbols in a block.
+    #[clap(long = "rs-n", default_value = "12")]
+    rs_n: usize,
+}
+
+impl Args {
+    /// Number of packets of the simulation, which requires it.
+    fn nb_packets(&self) -> u64 {
+        self.nb_packets.expect("-n is required without a command")
+    }
 }
 
 fn main() {
     env_logger::init();
 
     let args = Args::parse();
+    if let Some(Command::Fit { trace }) = &args.command {
+        fit_trace(trace).unwrap();
+        return;
+    }
     let mut simulator = Simulator::new();
+    simulator.set_timing(
+        Duration::from_micros(args.interval_us),
+        Duration::from_micros(args.tx_time_us),
+    );
+    if let Some(on_packets) = args.on_packets {
+        simulator.set_on_off(on_packets, Duration::from_micros(args.off_us));
+    }
 
     // Add dropper.
-    let drop_scheduler: Box<dyn DropScheduler> = match args.drop_scheduler {
-        DropS::None => Box::new(NoDropScheduler {}),
-        DropS::Constant => Box::new(ConstantDropScheduler::new(args.constant_loss_step)),
-        DropS::Uniform => Box::new(UniformDropScheduler::new(args.u_loss_ratio, args.drop_seed)),
-        DropS::GilbertEliot => Box::new(GilbertEliotDropSheduler::new_simple(
-            args.u_loss_ratio,
-            args.r_ge,
-            args.drop_seed,
-        )),
-        DropS::Specific => {
-            let mut scheduler = SpecificDropScheduler::new(100);
-            scheduler.add_to_drop(&[20, 21]);
-            Box::new(scheduler)
-        }
-    };
+    let seeds = SeedHierarchy::new(args.drop_seed);
+    let drop_scheduler = get_drop_scheduler(&args, seeds.seed(Component::Dropper));
     info!("Chosen drop scheduler: {:?}", drop_scheduler);
     let mut dropper = Dropper::new(drop_scheduler);
+    if args.crn {
+        dropper.set_common_random_numbers(seeds.seed(Component::DropperRepair));
+    }
     if args.drop_trace.is_some() {
         dropper.activate_trace();
     }
     simulator.set_dropper(dropper);
 
-    let (encoder, mut decoder) = match args.fec {
-        Fec::Maelstrom => get_maelstrom(&args),
-        Fec::Tart => get_tart(&args),
-        _ => (Encoder::new_simple(), Decoder::new_simple()),
+    // Add bit-error channel.
+    simulator.set_corrupter(Corrupter::new(args.ber, seeds.seed(Component::Corrupter)));
+
+    let registry = FecRegistry::default();
+    let (mut encoder, mut decoder) = match registry.create(&args.fec, &get_fec_params(&args)) {
+        Ok(nodes) => nodes,
+        Err(e) => {
+            error!("Cannot build FEC scheme: {:?}", e);
+            println!("Available FEC schemes: {}", registry.names().join(", "));
+            return;
+        }
     };
+    if args.checksum != "none" {
+        encoder.set_checksum(Checksum::from(args.checksum.as_str()));
+    }
     simulator.set_encoder(encoder);
     if args.rec_trace.is_some() {
         decoder.activate_trace();
     }
     simulator.set_decoder(decoder);
 
-    simulator.run(args.nb_packets).unwrap();
+    simulator.run(args.nb_packets()).unwrap();
 
     println!(
         "Nb recovered: {}",
@@ -192,7 +283,7 @@ fn main() {
     );
     println!(
         "And number missing: {}",
-        simulator.get_sink().get_lost(args.nb_packets).len()
+        simulator.get_sink().get_lost(args.nb_packets()).len()
     );
     println!(
         "Number of erased packets ssy: {}",
@@ -212,11 +303,36 @@ fn main() {
         simulator.get_encoder().get_nb_rs(),
         simulator.get_encoder().get_nb_ss()
     );
+    println!(
+        "Number of unused repair: {}",
+        simulator.get_decoder().get_nb_unused_rs()
+    );
+    println!(
+        "Number of feedback: {} ({} bytes)",
+        simulator.get_decoder().get_nb_feedback(),
+        simulator.get_decoder().get_nb_feedback_bytes()
+    );
     println!(
         "Number of duplicate packets: {} ({:?})",
         simulator.get_sink().get_duplicates().len(),
         simulator.get_sink().get_duplicates(),
     );
+    println!(
+        "Number of corrupted packets: {} (detected {}, undetected {} of which {} recovered)",
+        simulator.get_corrupter().get_nb_corrupted(),
+        simulator.get_decoder().get_nb_corrupted(),
+        simulator.get_sink().get_corrupted(args.nb_packets()).len(),
+        simulator
+            .get_sink()
+            .get_corrupted_recovered(args.nb_packets())
+            .len(),
+    );
+
+    let encoder_stats = simulator.get_encoder().get_fec_encoder().stats();
+    let decoder_stats = simulator.get_decoder().get_fec_decoder().stats();
+    for (name, value) in encoder_stats.iter().chain(decoder_stats.iter()) {
+        println!("FEC {}: {}", name, value);
+    }
 
     to_csv(&simulator, &args).unwrap();
 
@@ -240,49 +356,172 @@ fn main() {
     }
 }
 
-fn get_tart(args: &Args) -> (Encoder, Decoder) {
-    let scheduler: Box<dyn TartFecScheduler> = if args.tart_window {
-        Box::new(WindowStepScheduler::new(args.fec_window, 10))
-    } else {
-        let mut scheduler = AdaptiveFecScheduler::new(args.alpha_fec, args.fec_window);
-        if args.set_initial_loss {
-            scheduler.set_initial_loss_estimation(args.u_loss_ratio.max(1.0 / args.fec_window as f64));
+fn get_drop_scheduler(args: &Args, seed: u64) -> Box<dyn DropScheduler> {
+    match args.drop_scheduler {
+        DropS::None => Box::new(NoDropScheduler {}),
+        DropS::Constant => Box::new(ConstantDropScheduler::new(args.constant_loss_step)),
+        DropS::Uniform => Box::new(UniformDropScheduler::new(args.u_loss_ratio, seed)),
+        DropS::GilbertEliot => Box::new(GilbertEliotDropSheduler::new(
+            args.u_loss_ratio,
+            args.r_ge,
+            args.dg_ge,
+            args.db_ge,
+            seed,
+        )),
+        DropS::Specific => {
+            let mut scheduler = SpecificDropScheduler::new(100);
+            scheduler.add_to_drop(&[20, 21]);
+            Box::new(scheduler)
         }
-        scheduler.set_beta_fec(args.beta_fec);
-        scheduler.set_alpha_fec(args.alpha_fec);
-        Box::new(scheduler)
-    };
-    let tart_encoder = TartEncoder::new(scheduler, args.fec_window);
-    let encoder = Encoder::new(FecEncoder::Tart(tart_encoder));
+        DropS::Burst => {
+            let (gaps, bursts) = match args.burst_trace.as_ref() {
+                Some(filepath) => {
+                    let fit = LossModelFit::new(&read_drop_trace(filepath).unwrap());
+                    (
+                        LengthDistribution::empirical(fit.get_gaps()),
+                        LengthDistribution::empirical(fit.get_bursts()),
+                    )
+                }
+                None => (
+                    args.gap_dist.clone().expect("Missing --gap-dist"),
+                    args.burst_dist.clone().expect("Missing --burst-dist"),
+                ),
+            };
+            Box::new(BurstDropScheduler::new(gaps, bursts, seed))
+        }
+        DropS::GilbertEliotTime => Box::new(TimeGilbertEliotDropScheduler::new(
+            args.u_loss_ratio,
+            args.r_ge,
+            args.dg_ge,
+            args.db_ge,
+            Duration::from_micros(args.step_us),
+            seed,
+        )),
+        DropS::Outage => Box::new(OutageDropScheduler::new(
+            args.gap_dist.clone().expect("Missing --gap-dist"),
+            args.burst_dist.clone().expect("Missing --burst-dist"),
+            Duration::from_micros(args.step_us),
+            seed,
+        )),
+    }
+}
+
+/// Reads a drop trace (as generated by `--dtrace`). Returns whether each packet was dropped.
+fn read_drop_trace(filepath: &str) -> csv::Result<Vec<bool>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .from_path(filepath)?;
+
+    let mut drops = Vec::new();
+    for record in rdr.records() {
+        let record = record?;
+        drops.push(record.get(2).map(|v| v.trim() == "1").unwrap_or(false));
+    }
+
+    Ok(drops)
+}
+
+fn fit_trace(filepath: &str) -> csv::Result<()> {
+    let fit = LossModelFit::new(&read_drop_trace(filepath)?);
+    println!(
+        "Trace: {} packets, {} dropped",
+        fit.get_nb_pkts(),
+        fit.get_nb_dropped()
+    );
+
+    let rate = fit.get_uniform_rate();
+    println!("Uniform: rate={}", rate);
+    println!("    --drop uniform --u-loss {}", rate);
+
+    let gilbert = fit.get_gilbert();
+    println!("Simple Gilbert: p={} r={}", gilbert.g2b, gilbert.b2g);
+    println!("    {}", ge_args(gilbert));
 
-    let fec_decoder = FecDecoder::Tart(TartDecoder::new(args.fec_window));
-    let feedback = DecoderFeedback::new(args.feedback_freq);
-    let decoder = Decoder::new(fec_decoder, Some(feedback));
+    let ge = fit.get_gilbert_eliot();
+    println!(
+        "Gilbert-Elliot: p={} r={} dg={} db={} (log-likelihood {})",
+        ge.g2b,
+        ge.b2g,
+        ge.dg,
+        ge.db,
+        fit.get_log_likelihood()
+    );
+    println!("    {}", ge_args(ge));
 
-    (encoder, decoder)
+    println!("Burst lengths (mean {}):", fit.get_mean_burst());
+    for (len, nb) in fit.get_bursts() {
+        println!("    {}: {}", len, nb);
+    }
+    println!("Gap lengths (mean {}):", fit.get_mean_gap());
+    for (len, nb) in fit.get_gaps() {
+        println!("    {}: {}", len, nb);
+    }
+
+    Ok(())
 }
 
-fn get_maelstrom(args: &Args) -> (Encoder, Decoder) {
-    let encoder = MaelstromEncoder::new(args.fec_window as usize, &args.maelstrom_layering.layers);
-    let encoder = Encoder::new(FecEncoder::Maelstrom(encoder));
+fn ge_args(params: &GilbertEliotParams) -> String {
+    format!(
+        "--drop ge --u-loss {} -r {} --ge-dg {} --ge-db {}",
+        params.g2b, params.b2g, params.dg, params.db
+    )
+}
 
-    let decoder = MaelstromDecoder::new(args.fec_window as usize * 20);
-    let decoder = Decoder::new(FecDecoder::Maelstrom(decoder), None);
+/// Parameters of the FEC schemes from the command line arguments.
+fn get_fec_params(args: &Args) -> FecParams {
+    let mut params = FecParams::new();
+    params.set("window", args.fec_window);
+    params.set("feedback", args.feedback_freq);
+    if args.tart_window {
+        params.set("scheduler", "window");
+    }
+    params.set("alpha", args.alpha_fec);
+    params.set("beta", args.beta_fec);
+    if args.set_initial_loss {
+        params.set(
+            "initial-loss",
+            args.u_loss_ratio.max(1.0 / args.fec_window as f64),
+        );
+    }
+    params.set("layering", &args.maelstrom_layering);
+    if let Some(backend) = &args.tart_backend {
+        params.set("backend", backend);
+    }
+    params.set("symbol-size", args.symbol_size);
+    if let Some(factor) = args.window_factor {
+        params.set("window-factor", factor);
+    }
+    params.set("k", args.rs_k);
+    params.set("n", args.rs_n);
 
-    (encoder, decoder)
+    // Explicit parameters override the ones above.
+    let extra = FecParams::from(args.fec_params.as_str());
+    params.extend(&extra);
+    params
+}
+
+/// Name of the output files of a run, built from the input parameters.
+fn result_filename(simulator: &Simulator, args: &Args) -> String {
+    format!(
+        "{}-{:?}-{}-{}-{}{}{}.csv",
+        simulator.get_encoder().get_fec_encoder().name(),
+        args.drop_scheduler,
+        args.u_loss_ratio,
+        args.nb_packets(),
+        args.drop_seed,
+        if args.crn { "-crn" } else { "" },
+        if args.ber > 0.0 {
+            format!("-ber{}", args.ber)
+        } else {
+            String::new()
+        },
+    )
 }
 
 fn to_csv(simulator: &Simulator, args: &Args) -> std::io::Result<()> {
     fs::create_dir_all(&args.directory)?;
 
-    let pathname = format!(
-        "{:?}-{:?}-{}-{}-{}.csv",
-        simulator.get_encoder().get_fec_encoder(),
-        args.drop_scheduler,
-        args.u_loss_ratio,
-        args.nb_packets,
-        args.drop_seed
-    );
+    let pathname = result_filename(simulator, args);
     println!("Pathname: {:?}", &pathname);
     let path = std::path::Path::new(&args.directory).join(pathname);
 
@@ -297,10 +536,16 @@ fn to_csv(simulator: &Simulator, args: &Args) -> std::io::Result<()> {
         "n-ss-drop",
         "n-drop",
         "ratio,post",
+        "n-corrupt-detected",
+        "n-corrupt-undetected",
+        "n-corrupt-recovered",
+        "n-unused-repair",
+        "n-feedback",
+        "feedback-bytes",
     ])?;
     wrt.write_record(&[
         format!("{}", simulator.get_encoder().get_nb_rs()),
-        format!("{}", simulator.get_sink().get_lost(args.nb_packets).len()),
+        format!("{}", simulator.get_sink().get_lost(args.nb_packets()).len()),
         format!(
             "{}",
             simulator
@@ -311,19 +556,24 @@ fn to_csv(simulator: &Simulator, args: &Args) -> std::io::Result<()> {
         format!("{}", simulator.get_dropper().get_nb_ss_dropped()),
         format!("{}", simulator.get_dropper().get_nb_dropped()),
         format!("{}", simulator.get_dropper().get_dropped_ratio_posteriori()),
+        format!("{}", simulator.get_decoder().get_nb_corrupted()),
+        format!("{}", simulator.get_sink().get_corrupted(args.nb_packets()).len()),
+        format!(
+            "{}",
+            simulator
+                .get_sink()
+                .get_corrupted_recovered(args.nb_packets())
+                .len()
+        ),
+        format!("{}", simulator.get_decoder().get_nb_unused_rs()),
+        format!("{}", simulator.get_decoder().get_nb_feedback()),
+        format!("{}", simulator.get_decoder().get_nb_feedback_bytes()),
     ])?;
 
     if let Some(directory) = args.rec_trace.as_ref() {
         fs::create_dir_all(directory)?;
 
-        let pathname = format!(
-            "{:?}-{:?}-{}-{}-{}.csv",
-            simulator.get_encoder().get_fec_encoder(),
-            args.drop_scheduler,
-            args.u_loss_ratio,
-            args.nb_packets,
-            args.drop_seed
-        );
+        let pathname = result_filename(simulator, args);
         let path = std::path::Path::new(directory).join(pathname);
         let mut wrt = csv::WriterBuilder::new()
             .has_headers(true)
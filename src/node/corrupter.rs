@@ -0,0 +1,111 @@
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
+
+use crate::node::Node;
+use crate::node::Packet;
+use crate::Result;
+
+/// Bit-error channel. Flips bits of the packet payloads with a constant bit error rate (BER),
+/// so that the probability to corrupt a packet depends on its size.
+pub struct Corrupter {
+    /// Bit error rate.
+    ber: f64,
+
+    /// Number of bits to skip before the next bit error, over the stream of payload bits.
+    next_error: u64,
+
+    nb_recv: u64,
+
+    /// Number of packets with at least one bit error.
+    nb_corrupted: u64,
+
+    /// Number of flipped bits.
+    nb_bit_errors: u64,
+
+    pkts: Vec<Packet>,
+
+    rng: SmallRng,
+}
+
+impl Node for Corrupter {
+    fn recv(&mut self, pkts: Vec<Packet>) -> Result<()> {
+        self.nb_recv += pkts.len() as u64;
+        self.pkts.extend(pkts);
+        Ok(())
+    }
+
+    fn forw(&mut self) -> Result<Vec<Packet>> {
+        let mut out = Vec::with_capacity(self.pkts.len());
+        for mut pkt in self.pkts.drain(0..self.pkts.len()) {
+            if self.ber > 0.0 {
+                let nb_bits = pkt.data.len() as u64 * 8;
+                let mut bit: u64 = 0;
+                let mut corrupted = false;
+                while bit.saturating_add(self.next_error) < nb_bits {
+                    bit += self.next_error;
+                    pkt.data[(bit / 8) as usize] ^= 0x80 >> (bit % 8);
+                    self.nb_bit_errors += 1;
+                    corrupted = true;
+                    bit += 1;
+                    self.next_error = Self::draw_next_error(self.ber, &mut self.rng);
+                }
+                self.next_error -= nb_bits - bit;
+
+                if corrupted {
+                    self.nb_corrupted += 1;
+                }
+            }
+            out.push(pkt);
+        }
+        Ok(out)
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl Corrupter {
+    pub fn new(ber: f64, seed: u64) -> Self {
+        let mut corrupter = Self {
+            ber: ber.clamp(0.0, 1.0),
+            next_error: 0,
+            nb_recv: 0,
+            nb_corrupted: 0,
+            nb_bit_errors: 0,
+            pkts: Vec::new(),
+            rng: SmallRng::seed_from_u64(seed),
+        };
+        corrupter.next_error = Self::draw_next_error(corrupter.ber, &mut corrupter.rng);
+        corrupter
+    }
+
+    /// Channel without bit errors.
+    pub fn new_simple() -> Self {
+        Self::new(0.0, 0)
+    }
+
+    /// Number of error-free bits before the next bit error, following a geometric distribution.
+    fn draw_next_error(ber: f64, rng: &mut SmallRng) -> u64 {
+        if ber <= 0.0 {
+            return u64::MAX;
+        }
+        if ber >= 1.0 {
+            return 0;
+        }
+        let u = 1.0 - rng.gen::<f64>();
+        (u.ln() / (1.0 - ber).ln()).floor() as u64
+    }
+
+    pub fn get_nb_recv(&self) -> u64 {
+        self.nb_recv
+    }
+
+    pub fn get_nb_corrupted(&self) -> u64 {
+        self.nb_corrupted
+    }
+
+    pub fn get_nb_bit_errors(&self) -> u64 {
+        self.nb_bit_errors
+    }
+}
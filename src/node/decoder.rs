@@ -1,8 +1,7 @@
-use bitmaps::Bitmap;
-use networkcoding::source_symbol_metadata_to_u64;
+use std::collections::BTreeSet;
 
-use crate::fec::FecMetadata;
-use crate::{fec::FecDecoder, Packet};
+use crate::fec::{FecDecoderScheme, FecMetadata, NoFec};
+use crate::Packet;
 use crate::{Error, Result};
 
 /// Encoder structure.
@@ -16,11 +15,23 @@ pub struct Decoder {
     /// Number of recovered symbols.
     nb_recovered: u64,
 
+    /// Number of received packets whose checksum does not match the payload. They are treated as erasures.
+    nb_corrupted: u64,
+
+    /// Number of repair packets that protect no missing source symbol.
+    nb_unused_rs: u64,
+
+    /// Number of feedback messages sent.
+    nb_feedback: u64,
+
+    /// Total size of the feedback messages sent, in bytes.
+    nb_feedback_bytes: u64,
+
     /// (Ordered) pool of received packets that need to be processed.
     pkts: Vec<Packet>,
 
     /// FEC algorithm for the decoder.
-    fec: FecDecoder,
+    fec: Box<dyn FecDecoderScheme>,
 
     /// Feedback scheduler.
     feedback: Option<DecoderFeedback>,
@@ -35,21 +46,36 @@ impl Decoder {
         Ok(())
     }
 
-    pub fn forw(&mut self) -> Result<(Vec<Packet>, Vec<(u64, u64)>)> {
+    pub fn forw(&mut self) -> Result<(Vec<Packet>, Vec<FeedbackReport>)> {
         let mut out = Vec::with_capacity(self.pkts.len());
         let mut feedback_pkts = Vec::with_capacity(1);
 
         for mut pkt in self.pkts.drain(0..self.pkts.len()) {
+            if !pkt.verify_checksum() {
+                self.nb_corrupted += 1;
+                continue;
+            }
+
             match pkt.fec {
                 Some(FecMetadata::Source(_)) => {
                     self.nb_ss += 1;
 
+                    // Close the report windows ending before this source symbol, e.g., after losses
+                    // at the end of one, so that it and the symbols it recovers are in the window.
+                    let mut reports = match self.feedback.as_mut() {
+                        Some(feedback) => feedback.reports_before(pkt.id),
+                        None => Vec::new(),
+                    };
+
                     // Add packet to FEC window.
                     match self.fec.recv_ss(&pkt) {
                         Ok(recovered) => {
                             if !recovered.is_empty() {
                                 println!("Recovered packets from source symbol: {}", recovered.len());
                                 self.nb_recovered += recovered.len() as u64;
+                                if let Some(feedback) = self.feedback.as_mut() {
+                                    recovered.iter().for_each(|p| feedback.recv_recovered(p.id));
+                                }
                                 if let Some(trace) = self.trace.as_mut() {
                                     trace.extend(recovered.iter().map(|p| p.id));
                                 }
@@ -61,21 +87,20 @@ impl Decoder {
 
                     // Add packet to feedback.
                     if let Some(feedback) = self.feedback.as_mut() {
-                        let id = source_symbol_metadata_to_u64(
-                            pkt.data
-                                .to_owned()
-                                .try_into()
-                                .map_err(|_| Error::FecWrongMetadata)?,
-                        );
-                        feedback.recv_ss(id)?;
+                        // The payload may be corrupted on the link, unlike the packet ID.
+                        let id = pkt.id;
+                        feedback.recv_ss(id);
 
                         if feedback.should_send_feedback(id) {
-                            let total = feedback.nb_since_last(id);
-                            let nb_lost = total.saturating_sub(feedback.nb_recv());
-                            feedback_pkts.push((nb_lost, total));
+                            reports.push(feedback.report(id));
                             feedback.reset(id);
                         }
                     }
+                    for report in reports {
+                        self.nb_feedback += 1;
+                        self.nb_feedback_bytes += report.get_encoded_size() as u64;
+                        feedback_pkts.push(report);
+                    }
 
                     // Remove FEC.
                     pkt.fec = None;
@@ -90,6 +115,9 @@ impl Decoder {
                         Ok(recovered) => {
                             if !recovered.is_empty() {
                                 self.nb_recovered += recovered.len() as u64;
+                                if let Some(feedback) = self.feedback.as_mut() {
+                                    recovered.iter().for_each(|p| feedback.recv_recovered(p.id));
+                                }
                                 if let Some(trace) = self.trace.as_mut() {
                                     trace.extend(recovered.iter().map(|p| p.id));
                                 }
@@ -97,7 +125,10 @@ impl Decoder {
                             }
                         },
                         Err(Error::TooOldEquation) => debug!("Confirmed too old equation"),
-                        Err(Error::UnusedRepair) => debug!("Unused repair symbol. Do nothing."),
+                        Err(Error::UnusedRepair) => {
+                            debug!("Unused repair symbol. Do nothing.");
+                            self.nb_unused_rs += 1;
+                        }
                         Err(e) => error!("Error while decoding repair symbol: {e:?}"),
                     }
 
@@ -111,11 +142,15 @@ impl Decoder {
 }
 
 impl Decoder {
-    pub fn new(fec: FecDecoder, feedback: Option<DecoderFeedback>) -> Self {
+    pub fn new(fec: Box<dyn FecDecoderScheme>, feedback: Option<DecoderFeedback>) -> Self {
         Self {
             nb_ss: 0,
             nb_rs: 0,
             nb_recovered: 0,
+            nb_corrupted: 0,
+            nb_unused_rs: 0,
+            nb_feedback: 0,
+            nb_feedback_bytes: 0,
             pkts: Vec::new(),
             fec,
             feedback,
@@ -128,8 +163,12 @@ impl Decoder {
             nb_ss: 0,
             nb_rs: 0,
             nb_recovered: 0,
+            nb_corrupted: 0,
+            nb_unused_rs: 0,
+            nb_feedback: 0,
+            nb_feedback_bytes: 0,
             pkts: Vec::new(),
-            fec: FecDecoder::None,
+            fec: Box::new(NoFec),
             feedback: None,
             trace: None
         }
@@ -139,60 +178,334 @@ impl Decoder {
         self.nb_recovered
     }
 
+    pub fn get_nb_corrupted(&self) -> u64 {
+        self.nb_corrupted
+    }
+
+    pub fn get_nb_unused_rs(&self) -> u64 {
+        self.nb_unused_rs
+    }
+
+    pub fn get_nb_feedback(&self) -> u64 {
+        self.nb_feedback
+    }
+
+    pub fn get_nb_feedback_bytes(&self) -> u64 {
+        self.nb_feedback_bytes
+    }
+
     pub fn activate_trace(&mut self) {
         self.trace = Some(Vec::new())
     }
 
+    pub fn get_fec_decoder(&self) -> &dyn FecDecoderScheme {
+        self.fec.as_ref()
+    }
+
     pub fn get_trace(&self) -> Option<&[u64]> {
         self.trace.as_ref().map(|t| t.as_slice())
     }
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+/// Encoding of the loss pattern in a feedback message. Changes its size and what the encoder sees.
+pub enum LossEncoding {
+    /// One bit per source symbol.
+    #[default]
+    Bitmap,
+
+    /// Lengths of the alternating runs of received and lost source symbols, as varints.
+    RunLength,
+
+    /// No loss pattern, only the counters.
+    Summary,
+}
+
+impl From<&str> for LossEncoding {
+    fn from(value: &str) -> Self {
+        match value {
+            "bitmap" => Self::Bitmap,
+            "rle" => Self::RunLength,
+            "summary" => Self::Summary,
+            _ => Self::default(),
+        }
+    }
+}
+
 pub struct DecoderFeedback {
     /// Number of source symbols before sending feedback.
     frequency: u64,
 
-    /// Last feedback SSID.
-    last_feedback: u64,
+    /// First source symbol ID of this feedback.
+    first: u64,
+
+    /// Whether each source symbol of this feedback was received, from `first`.
+    received: Vec<bool>,
+
+    /// Whether each source symbol of this feedback was recovered, from `first`.
+    recovered: Vec<bool>,
+
+    /// Encoding of the loss pattern.
+    encoding: LossEncoding,
+
+    /// Whether the feedback acknowledges the source symbols received or recovered in order.
+    ack: bool,
 
-    /// Bitmap of received source symbols in this feedback.
-    bitmap: Bitmap<1024>,
+    /// All source symbols with a lower ID have been received or recovered.
+    next_in_order: u64,
+
+    /// Source symbols received or recovered after a missing one.
+    out_of_order: BTreeSet<u64>,
+
+    /// Missing source symbols this far before the newest one are given up.
+    ack_window: u64,
 }
 
 impl DecoderFeedback {
     pub fn new(frequency: u64) -> Self {
         Self {
             frequency,
-            last_feedback: 0,
-            bitmap: Bitmap::new(),
+            first: 0,
+            received: Vec::new(),
+            recovered: Vec::new(),
+            encoding: LossEncoding::default(),
+            ack: false,
+            next_in_order: 0,
+            out_of_order: BTreeSet::new(),
+            ack_window: u64::MAX,
         }
     }
 
-    pub fn recv_ss(&mut self, id: u64) -> Result<()> {
-        let relative_id = id - self.last_feedback;
-        if relative_id > 1024 {
-            return Err(Error::FeedbackIdTooBig);
-        }
+    /// Adds the highest source symbol received or recovered in order to the feedback.
+    pub fn set_ack(&mut self, ack: bool) {
+        self.ack = ack;
+    }
 
-        self.bitmap.set(relative_id as usize, true);
+    /// Gives up the missing source symbols more than `window` source symbols before the newest one,
+    /// e.g., evicted by the FEC decoder, so that they no longer hold back the acknowledgements
+    /// (default: never).
+    pub fn set_ack_window(&mut self, window: u64) {
+        self.ack_window = window;
+    }
 
-        Ok(())
+    pub fn set_encoding(&mut self, encoding: LossEncoding) {
+        self.encoding = encoding;
+    }
+
+    /// Source symbols outside the report window, i.e., the `frequency` symbols from the first one
+    /// of the next report, are ignored.
+    pub fn recv_ss(&mut self, id: u64) {
+        mark(&mut self.received, self.first, self.frequency, id);
+        self.recv_in_order(id);
+    }
+
+    /// Recovered source symbols count for the residual losses and the acknowledgements.
+    pub fn recv_recovered(&mut self, id: u64) {
+        mark(&mut self.recovered, self.first, self.frequency, id);
+        self.recv_in_order(id);
+    }
+
+    fn recv_in_order(&mut self, id: u64) {
+        if !self.ack || id < self.next_in_order {
+            return;
+        }
+        self.out_of_order.insert(id);
+        let oldest = id.saturating_sub(self.ack_window);
+        if oldest > self.next_in_order {
+            self.out_of_order = self.out_of_order.split_off(&oldest);
+            self.next_in_order = oldest;
+        }
+        while self.out_of_order.remove(&self.next_in_order) {
+            self.next_in_order += 1;
+        }
+    }
+
+    /// Report of the source symbols since the last feedback, up to `id` included.
+    pub fn report(&self, id: u64) -> FeedbackReport {
+        let nb_elems = self.nb_since_last(id) as usize;
+        let lost: Vec<bool> = (0..nb_elems)
+            .map(|i| !self.received.get(i).copied().unwrap_or(false))
+            .collect();
+        let nb_residual = lost
+            .iter()
+            .enumerate()
+            .filter(|&(i, &lost)| lost && !self.recovered.get(i).copied().unwrap_or(false))
+            .count();
+
+        let mut report = FeedbackReport::new(self.first, lost, self.encoding);
+        report.set_nb_residual(nb_residual as u64);
+        if self.ack && self.next_in_order > 0 {
+            report.set_highest_in_order(self.next_in_order - 1);
+        }
+        report
     }
 
     pub fn nb_recv(&self) -> u64 {
-        self.bitmap.len() as u64
+        self.received.iter().filter(|&&received| received).count() as u64
     }
 
     pub fn nb_since_last(&self, id: u64) -> u64 {
-        id - self.last_feedback
+        (id + 1).saturating_sub(self.first)
     }
 
     pub fn reset(&mut self, id: u64) {
-        self.last_feedback = id;
-        self.bitmap = Bitmap::new();
+        self.first = id + 1;
+        self.received.clear();
+        self.recovered.clear();
     }
 
     pub fn should_send_feedback(&self, id: u64) -> bool {
-        id - self.last_feedback >= self.frequency
+        self.nb_since_last(id) >= self.frequency
     }
+
+    /// Reports of the full report windows before `id`, which then starts in the current window.
+    pub fn reports_before(&mut self, id: u64) -> Vec<FeedbackReport> {
+        let mut reports = Vec::new();
+        while self.frequency > 0 && self.nb_since_last(id) > self.frequency {
+            let last = self.first + self.frequency - 1;
+            reports.push(self.report(last));
+            self.reset(last);
+        }
+        reports
+    }
+}
+
+/// Sets the flag of `id` in `flags`, which start at `first`.
+/// IDs outside of the `len` IDs from `first` are ignored.
+fn mark(flags: &mut Vec<bool>, first: u64, len: u64, id: u64) {
+    if id < first || id - first >= len {
+        return;
+    }
+    let index = (id - first) as usize;
+    if flags.len() <= index {
+        flags.resize(index + 1, false);
+    }
+    flags[index] = true;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Feedback message sent by the decoder to the encoder, about consecutive source symbols.
+pub struct FeedbackReport {
+    /// First source symbol of the report.
+    first: u64,
+
+    /// Number of source symbols of the report.
+    nb_elems: u64,
+
+    /// Number of source symbols lost on the link.
+    nb_lost: u64,
+
+    /// Number of lost source symbols that are still not recovered.
+    nb_residual: u64,
+
+    /// Lengths of the runs of consecutive lost source symbols.
+    bursts: Vec<u64>,
+
+    /// Whether each source symbol was lost, unless the encoding only carries the counters.
+    losses: Option<Vec<bool>>,
+
+    /// Encoding of the loss pattern.
+    encoding: LossEncoding,
+
+    /// Highest source symbol such that all source symbols up to it were received or recovered.
+    highest_in_order: Option<u64>,
+}
+
+/// Size of the counters of a feedback message: first source symbol (8 bytes), then number of source
+/// symbols, lost, residual and longest burst (4 bytes each).
+const FEEDBACK_HEADER_SIZE: usize = 24;
+
+impl FeedbackReport {
+    /// Report on the source symbols from `first`, with `lost` telling whether each one was lost.
+    pub fn new(first: u64, lost: Vec<bool>, encoding: LossEncoding) -> Self {
+        let runs = runs(&lost);
+        let bursts = runs.iter().skip(1).step_by(2).copied().filter(|&run| run > 0).collect();
+        Self {
+            first,
+            nb_elems: lost.len() as u64,
+            nb_lost: lost.iter().filter(|&&lost| lost).count() as u64,
+            nb_residual: 0,
+            bursts,
+            losses: (encoding != LossEncoding::Summary).then_some(lost),
+            encoding,
+            highest_in_order: None,
+        }
+    }
+
+    pub fn set_nb_residual(&mut self, nb_residual: u64) {
+        self.nb_residual = nb_residual;
+    }
+
+    pub fn set_highest_in_order(&mut self, id: u64) {
+        self.highest_in_order = Some(id);
+    }
+
+    pub fn get_first(&self) -> u64 {
+        self.first
+    }
+
+    pub fn get_nb_lost(&self) -> u64 {
+        self.nb_lost
+    }
+
+    pub fn get_nb_elems(&self) -> u64 {
+        self.nb_elems
+    }
+
+    pub fn get_nb_residual(&self) -> u64 {
+        self.nb_residual
+    }
+
+    pub fn get_max_burst(&self) -> u64 {
+        self.bursts.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Lengths of the bursts of losses, in order. Empty if the encoding only carries the counters.
+    pub fn get_bursts(&self) -> &[u64] {
+        match self.losses {
+            Some(_) => &self.bursts,
+            None => &[],
+        }
+    }
+
+    /// Whether each source symbol was lost, if the encoding carries the loss pattern.
+    pub fn get_losses(&self) -> Option<&[bool]> {
+        self.losses.as_deref()
+    }
+
+    pub fn get_highest_in_order(&self) -> Option<u64> {
+        self.highest_in_order
+    }
+
+    /// Size of the message in bytes, to account for the feedback overhead.
+    pub fn get_encoded_size(&self) -> usize {
+        let ack = if self.highest_in_order.is_some() { 8 } else { 0 };
+        let pattern = match (&self.losses, self.encoding) {
+            (Some(lost), LossEncoding::Bitmap) => lost.len().div_ceil(8),
+            (Some(lost), LossEncoding::RunLength) => {
+                runs(lost).iter().map(|&run| varint_size(run)).sum()
+            }
+            _ => 0,
+        };
+        FEEDBACK_HEADER_SIZE + ack + pattern
+    }
+}
+
+/// Lengths of the alternating runs of received and lost source symbols, from a received one.
+fn runs(lost: &[bool]) -> Vec<u64> {
+    let mut runs = vec![0];
+    let mut current = false;
+    for &lost in lost {
+        if lost != current {
+            runs.push(0);
+            current = lost;
+        }
+        *runs.last_mut().unwrap() += 1;
+    }
+    runs
+}
+
+/// Number of bytes of `value` encoded as a varint, i.e., 7 bits per byte.
+fn varint_size(value: u64) -> usize {
+    (64 - value.leading_zeros() as usize).div_ceil(7).max(1)
 }
@@ -4,6 +4,8 @@ use crate::fec::FecMetadata;
 use crate::node::Node;
 use crate::node::Packet;
 use crate::Result;
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
 
 pub type DropTrace = (u64, bool, bool);
 
@@ -11,6 +13,10 @@ pub type DropTrace = (u64, bool, bool);
 pub struct Dropper {
     scheduler: Box<dyn DropScheduler>,
 
+    /// Stream of the drop decisions of repair symbols, with common random numbers.
+    /// If `None`, `scheduler` draws the drop decisions of all packets.
+    repair_rng: Option<SmallRng>,
+
     nb_recv: u64,
 
     nb_drop: u64,
@@ -35,7 +41,15 @@ impl Node for Dropper {
             let is_repair = matches!(pkt.fec, Some(FecMetadata::Repair(_)));
             let id = pkt.id;
 
-            let is_dropped = if self.scheduler.should_drop() {
+            let should_drop = match self.repair_rng.as_mut() {
+                Some(rng) if is_repair => {
+                    let proba = self.scheduler.drop_probability_at(pkt.time);
+                    rng.gen_bool(proba.clamp(0.0, 1.0))
+                }
+                _ => self.scheduler.should_drop_at(pkt.time),
+            };
+
+            let is_dropped = if should_drop {
                 self.nb_drop += 1;
 
                 if let Some(FecMetadata::Source(_)) = pkt.fec {
@@ -64,6 +78,7 @@ impl Dropper {
     pub fn new(scheduler: Box<dyn DropScheduler>) -> Self {
         Self {
             scheduler,
+            repair_rng: None,
             nb_recv: 0,
             nb_drop: 0,
             nb_drop_ss: 0,
@@ -75,6 +90,7 @@ impl Dropper {
     pub fn new_simple() -> Self {
         Self {
             scheduler: Box::new(NoDropScheduler {}),
+            repair_rng: None,
             nb_drop: 0,
             nb_drop_ss: 0,
             nb_recv: 0,
@@ -83,6 +99,14 @@ impl Dropper {
         }
     }
 
+    /// Common random numbers: only the source symbols advance the drop scheduler, so that all FEC
+    /// schemes see the same source symbol losses whatever the number of repair symbols sent.
+    /// Repair symbols share the channel state of the last source symbol (e.g., its burst) and draw
+    /// their drop decisions from a distinct stream seeded with `seed`.
+    pub fn set_common_random_numbers(&mut self, seed: u64) {
+        self.repair_rng = Some(SmallRng::seed_from_u64(seed));
+    }
+
     pub fn get_nb_dropped(&self) -> u64 {
         self.nb_drop
     }
@@ -1,6 +1,11 @@
+use std::time::Duration;
+
+use crate::checksum::Checksum;
 use crate::Result;
 use crate::Error;
-use crate::{fec::FecEncoder, node::Node, Packet};
+use crate::fec::{FecEncoderScheme, NoFec};
+use crate::node::decoder::FeedbackReport;
+use crate::{node::Node, Packet};
 
 /// Encoder structure.
 pub struct Encoder {
@@ -14,7 +19,10 @@ pub struct Encoder {
     pkts: Vec<Packet>,
 
     /// FEC algorithm for the encoder.
-    fec: FecEncoder,
+    fec: Box<dyn FecEncoderScheme>,
+
+    /// Checksum added to the header of all sent packets.
+    checksum: Option<Checksum>,
 }
 
 impl Node for Encoder {
@@ -40,6 +48,7 @@ impl Node for Encoder {
             }
         }
 
+        self.add_checksum(&mut out);
         Ok(out)
     }
 
@@ -49,12 +58,13 @@ impl Node for Encoder {
 }
 
 impl Encoder {
-    pub fn new(fec: FecEncoder) -> Self {
+    pub fn new(fec: Box<dyn FecEncoderScheme>) -> Self {
         Self {
             nb_ss: 0,
             nb_rs: 0,
             pkts: Vec::new(),
             fec,
+            checksum: None,
         }
     }
 
@@ -63,10 +73,38 @@ impl Encoder {
             nb_ss: 0,
             nb_rs: 0,
             pkts: Vec::new(),
-            fec: FecEncoder::None,
+            fec: Box::new(NoFec),
+            checksum: None,
         }
     }
 
+    /// Adds a checksum of the payload to the header of all sent packets.
+    pub fn set_checksum(&mut self, checksum: Checksum) {
+        self.checksum = Some(checksum);
+    }
+
+    fn add_checksum(&self, pkts: &mut [Packet]) {
+        if let Some(checksum) = self.checksum {
+            pkts.iter_mut().for_each(|pkt| pkt.add_checksum(checksum));
+        }
+    }
+
+    /// Repair symbols due at virtual time `now`, before the next source symbol.
+    pub fn on_timer(&mut self, now: Duration) -> Result<Vec<Packet>> {
+        let mut repairs = self.fec.on_timer(now)?;
+        self.nb_rs += repairs.len() as u64;
+        self.add_checksum(&mut repairs);
+        Ok(repairs)
+    }
+
+    /// Repair symbols protecting the tail of the stream.
+    pub fn flush(&mut self) -> Result<Vec<Packet>> {
+        let mut repairs = self.fec.flush()?;
+        self.nb_rs += repairs.len() as u64;
+        self.add_checksum(&mut repairs);
+        Ok(repairs)
+    }
+
     pub fn get_nb_rs(&self) -> u64 {
         self.nb_rs
     }
@@ -75,13 +113,13 @@ impl Encoder {
         self.nb_ss
     }
 
-    pub fn recv_feedback(&mut self, feedback: Vec<(u64, u64)>) {
-        for (nb_lost, nb_elems) in feedback {
-            self.fec.recv_feedback(nb_lost, nb_elems);
+    pub fn recv_feedback(&mut self, feedback: Vec<FeedbackReport>) {
+        for report in feedback {
+            self.fec.recv_feedback(&report);
         }
     }
 
-    pub fn get_fec_encoder(&self) -> &FecEncoder {
-        &self.fec
+    pub fn get_fec_encoder(&self) -> &dyn FecEncoderScheme {
+        self.fec.as_ref()
     }
 }
@@ -3,6 +3,7 @@ use std::collections::HashSet;
 
 use crate::Packet;
 use crate::Result;
+pub mod corrupter;
 pub mod decoder;
 pub mod dropper;
 pub mod encoder;
@@ -72,6 +73,27 @@ impl Sink {
         self.recv.iter().filter(|pkt| pkt.recovered.is_some()).map(|pkt| (pkt.id, pkt.recovered.unwrap())).collect()
     }
 
+    /// Returns the list of packet IDs whose payload differs from the one generated by the source,
+    /// i.e., packets with undetected corruption. Also returns IDs that were never generated by the source
+    /// (greater or equal to `max_id`).
+    pub fn get_corrupted(&self, max_id: u64) -> Vec<u64> {
+        self.recv
+            .iter()
+            .filter(|pkt| pkt.id >= max_id || pkt.data != Packet::new(pkt.id).data)
+            .map(|pkt| pkt.id)
+            .collect()
+    }
+
+    /// Returns the list of recovered packet IDs with undetected corruption, i.e., corruption that propagated through the FEC decoder.
+    pub fn get_corrupted_recovered(&self, max_id: u64) -> Vec<u64> {
+        self.recv
+            .iter()
+            .filter(|pkt| pkt.recovered.is_some())
+            .filter(|pkt| pkt.id >= max_id || pkt.data != Packet::new(pkt.id).data)
+            .map(|pkt| pkt.id)
+            .collect()
+    }
+
     /// Returns the list of packet IDs that are lost.
     pub fn get_lost(&self, max_id: u64) -> Vec<u64> {
         let recv: HashSet<u64> = self.recv.iter().map(|pkt| pkt.id).collect();
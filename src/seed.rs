@@ -0,0 +1,47 @@
+/// Components of the simulator that own a random number generator.
+/// Each component draws from its own stream, derived from a single master seed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Component {
+    /// Drop decisions of the dropper.
+    Dropper,
+
+    /// Drop decisions for repair symbols, when the dropper uses common random numbers.
+    DropperRepair,
+
+    /// Bit errors of the corrupter.
+    Corrupter,
+}
+
+/// Master seed from which every component derives its random stream.
+#[derive(Clone, Copy, Debug)]
+pub struct SeedHierarchy {
+    master: u64,
+}
+
+impl SeedHierarchy {
+    pub fn new(master: u64) -> Self {
+        Self { master }
+    }
+
+    pub fn get_master(&self) -> u64 {
+        self.master
+    }
+
+    /// Seed of the random stream of a component.
+    pub fn seed(&self, component: Component) -> u64 {
+        match component {
+            // The dropper directly uses the master seed so that results remain reproducible
+            // with those obtained before the seed hierarchy.
+            Component::Dropper => self.master,
+            other => splitmix64(self.master ^ splitmix64(other as u64 + 1)),
+        }
+    }
+}
+
+/// SplitMix64 finalizer. Spreads close inputs (e.g., consecutive seeds) over the whole 64-bit space.
+fn splitmix64(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}